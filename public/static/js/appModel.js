@@ -11,11 +11,22 @@ define([ "knockout", "reqwest", "moment", "messageModel", "chartModel" ], functi
 		}
 	};
 
+	const byGroup = function(a, b) {
+		if (a.group < b.group) {
+			return -1;
+		} else if (a.group > b.group) {
+			return 1;
+		} else {
+			return 0;
+		}
+	};
+
 	return function() {
 		const self = this;
 
 		this.messages = ko.observableArray([]);
 		this.products = ko.observableArray([]);
+		this.groups = ko.observableArray([]);
 		this.chart = new chart();
 		this.chartCategoryName = ko.observable("");
 		this.lastUpdate = ko.observable(null);
@@ -52,6 +63,56 @@ define([ "knockout", "reqwest", "moment", "messageModel", "chartModel" ], functi
 			return this.products().length > 0;
 		}, this);
 
+		this.isGroupTableVisible = ko.pureComputed(function() {
+			return this.groups().length > 0;
+		}, this);
+
+		this.groupShops = ko.pureComputed(function() {
+			const shops = [];
+
+			this.groups().forEach(function(group) {
+				group.offers.forEach(function(offer) {
+					if (shops.indexOf(offer.shop) < 0) {
+						shops.push(offer.shop);
+					}
+				});
+			});
+
+			return shops.sort();
+		}, this);
+
+		// Every group row contains one cell per shop, so prices of the same item are shown side by side
+		this.groupRows = ko.pureComputed(function() {
+			const shops = this.groupShops();
+
+			return this.groups().map(function(group) {
+				const offers = shops.map(function(shop) {
+					const offer = group.offers.find(function(offer) {
+						return offer.shop === shop;
+					});
+
+					if (offer) {
+						return {
+							hasOffer: true,
+							isBest: offer.price === group.price,
+							price: offer.price,
+							url: offer.url
+						};
+					} else {
+						return { hasOffer: false, isBest: false, price: 0, url: "" };
+					}
+				});
+
+				return {
+					group: group.group,
+					shop: group.shop,
+					price: group.price,
+					url: group.url,
+					offers: offers
+				};
+			});
+		}, this);
+
 		this.isChartVisible = ko.pureComputed(function() {
 			return this.chart.isVisible();
 		}, this);
@@ -112,12 +173,31 @@ define([ "knockout", "reqwest", "moment", "messageModel", "chartModel" ], functi
 			});
 		};
 
+		this.loadGroups = function() {
+			reqwest({
+				url: "/api/v1/group",
+				method: "get",
+				type: "json",
+				contentType: "application/json"
+			}).then(function (resp) {
+				if (resp.ok) {
+					self.groups(resp.groups.sort(byGroup));
+				} else {
+					self.messages.push(message.warn(resp.message, "Product groups"));
+				}
+			}).fail(function (err) {
+				self.messages.push(message.error("Failed to load product groups", "Product groups"));
+			});
+		};
+
 		this.updateProducts = function() {
 			if (!self.isProductsLoading()) {
 				this.loadProducts();
+				this.loadGroups();
 			}
 		};
 
 		this.loadProducts();
+		this.loadGroups();
 	};
 });
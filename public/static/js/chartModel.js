@@ -18,6 +18,7 @@ define([ "knockout", "moment" ], function(ko, moment) {
 		this.options = ko.observable({
 			width: 1097,
 			height: 320,
+			connectSeparatedPoints: true,
 			drawGapEdgePoints: true,
 			highlightCircleSize: 2.5,
 			labelsSeparateLines: true,
@@ -38,47 +39,32 @@ define([ "knockout", "moment" ], function(ko, moment) {
 
 		this.setData = function(data) {
 			const labels = [ "Update time" ];
-			const iterations = [];
+			const timestamps = [];
 			const data_map = {};
 			const data_sample = data.map(function() {
 				return null;
 			});
 
+			// Products are updated by their own schedules, so prices are aligned by update time.
 			data.forEach(function (item, index) {
 				labels.push(item.product);
 
 				item.prices.forEach(function (price) {
-					const iteration = price.iteration;
+					const timestamp = price.timestamp;
 
-					if (!data_map.hasOwnProperty(iteration)) {
-						iterations.push(iteration);
-
-						const values = data_sample.slice([]);
-
-						values[index] = price.price;
-						data_map[iteration] = {
-							count: 1,
-							timestamp_sum: price.timestamp,
-							values: values,
-						};
-					} else {
-						const data_entry = data_map[iteration];
-
-						data_entry.count += 1;
-						data_entry.timestamp_sum += price.timestamp;
-						data_entry.values[index] = price.price;
+					if (!data_map.hasOwnProperty(timestamp)) {
+						timestamps.push(timestamp);
+						data_map[timestamp] = data_sample.slice([]);
 					}
+
+					data_map[timestamp][index] = price.price;
 				});
 			});
 
-			iterations.sort(byIntegerValue);
-
-			const data_points = iterations.map(function(iteration) {
-				const data_entry = data_map[iteration];
-				const timestamp = data_entry.timestamp_sum / data_entry.count;
-				const values = [ moment.unix(timestamp).toDate() ].concat(data_entry.values);
+			timestamps.sort(byIntegerValue);
 
-				return values;
+			const data_points = timestamps.map(function(timestamp) {
+				return [ moment.unix(timestamp).toDate() ].concat(data_map[timestamp]);
 			});
 
 			self.options().labels = labels;
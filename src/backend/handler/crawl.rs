@@ -0,0 +1,155 @@
+use std::error::Error;
+
+use iron::Handler;
+use iron::IronResult;
+use iron::mime::Mime;
+use iron::Plugin;
+use iron::Request;
+use iron::Response;
+use iron::status;
+use serde_json;
+use urlencoded::UrlEncodedQuery;
+
+use worker::CrawlerControl;
+use worker::CrawlFilter;
+use worker::CrawlStatus;
+
+
+pub struct CrawlHandler {
+    control: Option<CrawlerControl>,
+}
+
+
+pub struct CrawlStatusHandler {
+    control: Option<CrawlerControl>,
+}
+
+
+#[derive(Serialize)]
+struct HandlerResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<ResponseStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+
+#[derive(Serialize)]
+struct ResponseStatus {
+    running: bool,
+    pending: usize,
+    processed: usize,
+    total: usize,
+    started: Option<i64>,
+    completed: Option<i64>,
+}
+
+
+impl HandlerResponse {
+    fn ok(status: ResponseStatus) -> HandlerResponse {
+        HandlerResponse {
+            ok: true,
+            status: Some(status),
+            message: None,
+        }
+    }
+
+    fn accepted<S>(status: ResponseStatus, message: S) -> HandlerResponse
+    where
+        S: Into<String>,
+    {
+        HandlerResponse {
+            ok: true,
+            status: Some(status),
+            message: Some(message.into()),
+        }
+    }
+
+    fn err<S>(message: S) -> HandlerResponse
+    where
+        S: Into<String>,
+    {
+        HandlerResponse {
+            ok: false,
+            status: None,
+            message: Some(message.into()),
+        }
+    }
+}
+
+
+impl ResponseStatus {
+    fn new(crawl_status: &CrawlStatus) -> ResponseStatus {
+        ResponseStatus {
+            running: crawl_status.is_running(),
+            pending: crawl_status.pending(),
+            processed: crawl_status.processed(),
+            total: crawl_status.total(),
+            started: crawl_status.started(),
+            completed: crawl_status.completed(),
+        }
+    }
+}
+
+
+impl CrawlHandler {
+    pub fn new(control: Option<CrawlerControl>) -> CrawlHandler {
+        CrawlHandler { control }
+    }
+}
+
+
+impl CrawlStatusHandler {
+    pub fn new(control: Option<CrawlerControl>) -> CrawlStatusHandler {
+        CrawlStatusHandler { control }
+    }
+}
+
+
+impl Handler for CrawlHandler {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        let content_type: Mime = check_text!("application/json".parse(), "MIME type parsing error");
+        let shop: Option<String> = check_optional_value!(content_type, request, "shop");
+        let category: Option<String> = check_optional_value!(content_type, request, "category");
+        let product: Option<String> = check_optional_value!(content_type, request, "product");
+        let response = match self.control {
+            Some(ref control) => {
+                let filter = CrawlFilter::new(shop, category, product);
+                match control.request_crawl(filter) {
+                    Ok(queued) => {
+                        let message = if queued {
+                            "Crawl requested"
+                        } else {
+                            "Identical crawl is already requested"
+                        };
+
+                        HandlerResponse::accepted(ResponseStatus::new(&control.status()), message)
+                    }
+                    Err(error) => HandlerResponse::err(format!("{}", error)),
+                }
+            }
+            None => HandlerResponse::err("Crawler is disabled"),
+        };
+        let body = check_error!(serde_json::to_string(&response));
+
+        Ok(Response::with((content_type, status::Ok, body)))
+    }
+}
+
+
+impl Handler for CrawlStatusHandler {
+    fn handle(&self, _: &mut Request) -> IronResult<Response> {
+        let content_type: Mime = check_text!("application/json".parse(), "MIME type parsing error");
+        let response = match self.control {
+            Some(ref control) => {
+                HandlerResponse::ok(ResponseStatus::new(&control.status()))
+            }
+            None => HandlerResponse::err("Crawler is disabled"),
+        };
+        let body = check_error!(serde_json::to_string(&response));
+
+        Ok(Response::with((content_type, status::Ok, body)))
+    }
+}
+
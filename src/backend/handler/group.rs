@@ -0,0 +1,114 @@
+use std::error::Error;
+
+use iron::Handler;
+use iron::IronResult;
+use iron::mime::Mime;
+use iron::Request;
+use iron::Response;
+use iron::status;
+use serde_json;
+
+use database::Database;
+use database::GroupPrice;
+
+
+pub struct GroupHandler {
+    database: Database,
+}
+
+
+#[derive(Serialize)]
+struct HandlerResponse {
+    ok: bool,
+    groups: Vec<ResponseGroup>,
+}
+
+
+#[derive(Serialize)]
+struct ResponseGroup {
+    group_id: i64,
+    group: String,
+    shop: String,
+    price: f64,
+    url: String,
+    offers: Vec<ResponseOffer>,
+}
+
+
+#[derive(Serialize)]
+struct ResponseOffer {
+    product: String,
+    url: String,
+    shop: String,
+    price: f64,
+    updated: i64,
+}
+
+
+impl HandlerResponse {
+    fn ok(groups: Vec<ResponseGroup>) -> HandlerResponse {
+        HandlerResponse { ok: true, groups }
+    }
+}
+
+
+impl ResponseGroup {
+    fn new(group_price: &GroupPrice) -> ResponseGroup {
+        ResponseGroup {
+            group_id: group_price.group_id(),
+            group: group_price.group(),
+            shop: group_price.shop(),
+            price: group_price.price(),
+            url: group_price.url(),
+            offers: Vec::new(),
+        }
+    }
+}
+
+
+impl ResponseOffer {
+    fn new(group_price: &GroupPrice) -> ResponseOffer {
+        ResponseOffer {
+            product: group_price.product(),
+            url: group_price.url(),
+            shop: group_price.shop(),
+            price: group_price.price(),
+            updated: group_price.timestamp(),
+        }
+    }
+}
+
+
+impl GroupHandler {
+    pub fn new(database: Database) -> GroupHandler {
+        GroupHandler { database }
+    }
+}
+
+
+impl Handler for GroupHandler {
+    fn handle(&self, _: &mut Request) -> IronResult<Response> {
+        let content_type: Mime = check_text!("application/json".parse(), "MIME type parsing error");
+        let mut groups: Vec<ResponseGroup> = Vec::new();
+
+        for group_price in check_error!(self.database.group_prices()) {
+            let is_new_group = match groups.last() {
+                Some(group) => group.group_id != group_price.group_id(),
+                None => true,
+            };
+
+            if is_new_group {
+                groups.push(ResponseGroup::new(&group_price));
+            }
+
+            if let Some(group) = groups.last_mut() {
+                group.offers.push(ResponseOffer::new(&group_price));
+            }
+        }
+
+        let response = HandlerResponse::ok(groups);
+        let body = check_error!(serde_json::to_string(&response));
+
+        Ok(Response::with((content_type, status::Ok, body)))
+    }
+}
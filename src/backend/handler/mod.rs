@@ -1,10 +1,15 @@
 #[macro_use]
 mod util;
 
+mod crawl;
 mod empty;
+mod group;
 mod price;
 mod product;
 
+pub use self::crawl::CrawlHandler;
+pub use self::crawl::CrawlStatusHandler;
 pub use self::empty::EmptyHandler;
+pub use self::group::GroupHandler;
 pub use self::price::PriceHandler;
 pub use self::product::ProductHandler;
@@ -100,11 +100,16 @@ impl PriceHandler {
 impl Handler for PriceHandler {
     fn handle(&self, request: &mut Request) -> IronResult<Response> {
         let content_type: Mime = check_text!("application/json".parse(), "MIME type parsing error");
+        let tag: Option<String> = check_optional_value!(content_type, request, "tag");
         let params = check_params!(request, content_type);
         let category_id = check_value!(content_type, params, "category");
         let mut product_prices = Vec::new();
 
-        for product in check_error!(self.database.products_by_category(category_id)) {
+        for product in check_error!(self.database.products_by_category(
+            category_id,
+            tag.as_ref().map(|tag| tag.as_str()),
+        ))
+        {
             let mut prices = Vec::new();
 
             for product_price in check_error!(
@@ -4,10 +4,12 @@ use std::error::Error;
 use iron::Handler;
 use iron::IronResult;
 use iron::mime::Mime;
+use iron::Plugin;
 use iron::Request;
 use iron::Response;
 use iron::status;
 use serde_json;
+use urlencoded::UrlEncodedQuery;
 
 use database::Database;
 use database::IterationPrice;
@@ -21,7 +23,10 @@ pub struct ProductHandler {
 #[derive(Serialize)]
 struct HandlerResponse {
     ok: bool,
-    products: Vec<ResponseProduct>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    products: Option<Vec<ResponseProduct>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
 }
 
 
@@ -41,7 +46,19 @@ impl HandlerResponse {
     fn ok(products: Vec<ResponseProduct>) -> HandlerResponse {
         HandlerResponse {
             ok: true,
-            products: products,
+            products: Some(products),
+            message: None,
+        }
+    }
+
+    fn err<S>(message: S) -> HandlerResponse
+    where
+        S: Into<String>,
+    {
+        HandlerResponse {
+            ok: false,
+            products: None,
+            message: Some(message.into()),
         }
     }
 }
@@ -84,41 +101,61 @@ impl ProductHandler {
 
 
 impl Handler for ProductHandler {
-    fn handle(&self, _: &mut Request) -> IronResult<Response> {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
         let content_type: Mime = check_text!("application/json".parse(), "MIME type parsing error");
-        let iteration = check_error!(self.database.iteration());
+        let category_filter: Option<i64> =
+            check_optional_value!(content_type, request, "category");
+        let tag: Option<String> = check_optional_value!(content_type, request, "tag");
+        let categories = check_error!(self.database.categories());
+        let mut parents = HashMap::new();
+        let mut names = HashMap::new();
+        let mut products_by_category: HashMap<_, IterationPrice> = HashMap::new();
         let mut best_products = Vec::new();
 
-        if let Some(iteration) = iteration {
-            let mut products_by_category: HashMap<_, IterationPrice> = HashMap::new();
+        for category in categories {
+            parents.insert(category.id(), category.parent_id());
+            names.insert(category.id(), category.name());
+        }
+
+        for product_price in check_error!(self.database.latest_product_prices(
+            tag.as_ref().map(|tag| tag.as_str()),
+        ))
+        {
+            let mut category_id = Some(product_price.category_id());
 
-            for product_price in check_error!(self.database.product_price_by_iteration(
-                iteration,
-                iteration,
-            ))
-            {
-                let category_id = product_price.category_id();
-                let insert_product = match products_by_category.get(&category_id) {
+            while let Some(id) = category_id {
+                let insert_product = match products_by_category.get(&id) {
                     Some(selected_price) if selected_price.price() < product_price.price() => false,
                     Some(_) | None => true,
                 };
 
                 if insert_product {
-                    products_by_category.insert(category_id, product_price);
+                    products_by_category.insert(id, product_price.clone());
                 }
+
+                category_id = parents.get(&id).and_then(|&parent_id| parent_id);
             }
+        }
 
-            for (category_id, product_price) in products_by_category {
-                best_products.push(ResponseProduct::new(
-                    category_id,
-                    product_price.category(),
-                    product_price.product(),
-                    product_price.url(),
-                    product_price.shop(),
-                    product_price.price(),
-                    product_price.timestamp(),
-                ));
+        for (category_id, product_price) in products_by_category {
+            if let Some(filter_id) = category_filter {
+                if !is_descendant(&parents, category_id, filter_id) {
+                    continue;
+                }
             }
+
+            best_products.push(ResponseProduct::new(
+                category_id,
+                names
+                    .get(&category_id)
+                    .cloned()
+                    .unwrap_or_else(|| product_price.category()),
+                product_price.product(),
+                product_price.url(),
+                product_price.shop(),
+                product_price.price(),
+                product_price.timestamp(),
+            ));
         }
 
         let response = HandlerResponse::ok(best_products);
@@ -127,3 +164,18 @@ impl Handler for ProductHandler {
         Ok(Response::with((content_type, status::Ok, body)))
     }
 }
+
+
+fn is_descendant(parents: &HashMap<i64, Option<i64>>, category_id: i64, ancestor_id: i64) -> bool {
+    let mut current = Some(category_id);
+
+    while let Some(id) = current {
+        if id == ancestor_id {
+            return true;
+        }
+
+        current = parents.get(&id).and_then(|&parent_id| parent_id);
+    }
+
+    false
+}
@@ -96,3 +96,36 @@ macro_rules! check_value {
         }
     }};
 }
+
+macro_rules! check_optional_value {
+    ( $content_type: expr, $request: expr, $name: expr ) => {{
+        let value = match $request.get_ref::<UrlEncodedQuery>() {
+            Ok(params) => params.get($name).and_then(|values| values.get(0)).cloned(),
+            Err(_) => None,
+        };
+
+        match value {
+            Some(value) => {
+                match value.parse() {
+                    Ok(result) => Some(result),
+                    Err(_) => {
+                        let response =
+                            HandlerResponse::err(format!("Parameter {} has invalid value", $name));
+
+                        match serde_json::to_string(&response) {
+                            Ok(body) => {
+                                return Ok(Response::with(($content_type, status::Ok, body)))
+                            }
+                            Err(error) => {
+                                warn!("Handler error: {}", error.description());
+
+                                return Ok(Response::with(status::InternalServerError));
+                            }
+                        }
+                    }
+                }
+            }
+            None => None,
+        }
+    }};
+}
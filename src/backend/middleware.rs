@@ -0,0 +1,83 @@
+use std::io::Result as IoResult;
+use std::io::Write;
+
+use iron::AroundMiddleware;
+use iron::Handler;
+use iron::IronResult;
+use iron::Request;
+use iron::Response;
+use iron::response::WriteBody;
+use iron::status;
+
+use shutdown::Shutdown;
+use shutdown::WorkGuard;
+
+
+pub struct ShutdownMiddleware {
+    shutdown: Shutdown,
+}
+
+
+struct ShutdownHandler {
+    shutdown: Shutdown,
+    handler: Box<Handler>,
+}
+
+
+struct GuardedBody {
+    body: Box<WriteBody>,
+    _work: WorkGuard,
+}
+
+
+impl ShutdownMiddleware {
+    pub fn new(shutdown: Shutdown) -> ShutdownMiddleware {
+        ShutdownMiddleware { shutdown }
+    }
+}
+
+
+impl AroundMiddleware for ShutdownMiddleware {
+    fn around(self, handler: Box<Handler>) -> Box<Handler> {
+        Box::new(ShutdownHandler {
+            shutdown: self.shutdown,
+            handler,
+        })
+    }
+}
+
+
+impl Handler for ShutdownHandler {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        let work = self.shutdown.work();
+
+        if self.shutdown.is_requested() {
+            return Ok(Response::with((status::ServiceUnavailable, "Server is shutting down")));
+        }
+
+        match self.handler.handle(request) {
+            Ok(response) => Ok(guard_body(response, work)),
+            Err(mut error) => {
+                error.response = guard_body(error.response, work);
+
+                Err(error)
+            }
+        }
+    }
+}
+
+
+impl WriteBody for GuardedBody {
+    fn write_body(&mut self, res: &mut Write) -> IoResult<()> {
+        self.body.write_body(res)
+    }
+}
+
+
+fn guard_body(mut response: Response, work: WorkGuard) -> Response {
+    response.body = response.body.take().map(|body| {
+        Box::new(GuardedBody { body, _work: work }) as Box<WriteBody>
+    });
+
+    response
+}
@@ -1,5 +1,6 @@
 mod error;
 mod handler;
+mod middleware;
 mod starter;
 
 pub use self::error::BackendError;
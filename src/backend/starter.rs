@@ -1,24 +1,37 @@
+use iron::Chain;
 use iron::Iron;
+use iron::Listening;
 use mount::Mount;
 use router::Router;
 use staticfile::Static;
 
 use database::Database;
+use shutdown::Shutdown;
+use worker::CrawlerControl;
 
 use super::error::BackendError;
+use super::handler::CrawlHandler;
+use super::handler::CrawlStatusHandler;
 use super::handler::EmptyHandler;
+use super::handler::GroupHandler;
 use super::handler::PriceHandler;
 use super::handler::ProductHandler;
+use super::middleware::ShutdownMiddleware;
 
 
 pub fn start_backend(
     database: Database,
+    control: Option<CrawlerControl>,
+    shutdown: Shutdown,
     bind_address: &str,
     bind_port: u16,
-) -> Result<(), BackendError> {
+) -> Result<Listening, BackendError> {
     let mut router = Router::new();
     router.get("/price", PriceHandler::new(database.clone()), "price");
-    router.get("/product", ProductHandler::new(database), "product");
+    router.get("/product", ProductHandler::new(database.clone()), "product");
+    router.get("/group", GroupHandler::new(database), "group");
+    router.get("/crawl", CrawlStatusHandler::new(control.clone()), "crawl_status");
+    router.post("/crawl", CrawlHandler::new(control), "crawl");
     router.get("/", EmptyHandler::new(), "empty");
 
     let mut mount = Mount::new();
@@ -26,9 +39,12 @@ pub fn start_backend(
     mount.mount("/api/v1", router);
     mount.mount("/", Static::new("public/index.html"));
 
+    let mut chain = Chain::new(mount);
+    chain.link_around(ShutdownMiddleware::new(shutdown));
+
     info!("Starting WEB server {}:{}", bind_address, bind_port);
 
-    Iron::new(mount).http((bind_address, bind_port))?;
+    let listening = Iron::new(chain).http((bind_address, bind_port))?;
 
-    Ok(())
+    Ok(listening)
 }
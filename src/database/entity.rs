@@ -127,3 +127,256 @@ impl IterationPrice {
         self.timestamp
     }
 }
+
+
+#[derive(Debug, Clone)]
+pub struct PriceReading {
+    price: f64,
+    quarantine: Option<String>,
+}
+
+
+impl PriceReading {
+    #[inline]
+    pub fn new(price: f64, quarantine: Option<String>) -> PriceReading {
+        PriceReading { price, quarantine }
+    }
+
+    #[inline]
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    #[inline]
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantine.is_some()
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct NameChange {
+    old_name: String,
+    new_name: String,
+    substitution: bool,
+}
+
+
+impl NameChange {
+    #[inline]
+    pub fn new(old_name: String, new_name: String, substitution: bool) -> NameChange {
+        NameChange {
+            old_name,
+            new_name,
+            substitution,
+        }
+    }
+
+    #[inline]
+    pub fn old_name(&self) -> String {
+        self.old_name.clone()
+    }
+
+    #[inline]
+    pub fn new_name(&self) -> String {
+        self.new_name.clone()
+    }
+
+    #[inline]
+    pub fn is_substitution(&self) -> bool {
+        self.substitution
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct GroupPrice {
+    group_id: i64,
+    group: String,
+    product: String,
+    url: String,
+    shop: String,
+    price: f64,
+    timestamp: i64,
+}
+
+
+impl GroupPrice {
+    #[inline]
+    pub fn new(
+        group_id: i64,
+        group: String,
+        product: String,
+        url: String,
+        shop: String,
+        price: f64,
+        timestamp: i64,
+    ) -> GroupPrice {
+        GroupPrice {
+            group_id,
+            group,
+            product,
+            url,
+            shop,
+            price,
+            timestamp,
+        }
+    }
+
+    #[inline]
+    pub fn group_id(&self) -> i64 {
+        self.group_id
+    }
+
+    #[inline]
+    pub fn group(&self) -> String {
+        self.group.clone()
+    }
+
+    #[inline]
+    pub fn product(&self) -> String {
+        self.product.clone()
+    }
+
+    #[inline]
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    #[inline]
+    pub fn shop(&self) -> String {
+        self.shop.clone()
+    }
+
+    #[inline]
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Category {
+    id: i64,
+    name: String,
+    parent_id: Option<i64>,
+}
+
+
+impl Category {
+    #[inline]
+    pub fn new(id: i64, name: String, parent_id: Option<i64>) -> Category {
+        Category {
+            id,
+            name,
+            parent_id,
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[inline]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[inline]
+    pub fn parent_id(&self) -> Option<i64> {
+        self.parent_id
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceRecord {
+    shop: String,
+    category: String,
+    url: String,
+    name: String,
+    group: Option<String>,
+    iteration: i64,
+    timestamp: i64,
+    price: f64,
+    quarantine: Option<String>,
+}
+
+
+impl PriceRecord {
+    #[inline]
+    pub fn new(
+        shop: String,
+        category: String,
+        url: String,
+        name: String,
+        group: Option<String>,
+        iteration: i64,
+        timestamp: i64,
+        price: f64,
+        quarantine: Option<String>,
+    ) -> PriceRecord {
+        PriceRecord {
+            shop,
+            category,
+            url,
+            name,
+            group,
+            iteration,
+            timestamp,
+            price,
+            quarantine,
+        }
+    }
+
+    #[inline]
+    pub fn shop(&self) -> String {
+        self.shop.clone()
+    }
+
+    #[inline]
+    pub fn category(&self) -> String {
+        self.category.clone()
+    }
+
+    #[inline]
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    #[inline]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[inline]
+    pub fn group(&self) -> Option<String> {
+        self.group.clone()
+    }
+
+    #[inline]
+    pub fn iteration(&self) -> i64 {
+        self.iteration
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    #[inline]
+    pub fn quarantine(&self) -> Option<String> {
+        self.quarantine.clone()
+    }
+}
@@ -0,0 +1,91 @@
+use sqlite::Connection;
+use sqlite::State;
+use sqlite::Value;
+
+use super::DatabaseError;
+
+
+const MIGRATIONS: &'static [(i64, &'static str)] = &[
+    (1, include_str!("migration/0001_price_quarantine.sql")),
+    (2, include_str!("migration/0002_product_name_history.sql")),
+    (3, include_str!("migration/0003_product_schedule.sql")),
+    (4, include_str!("migration/0004_product_group.sql")),
+    (5, include_str!("migration/0005_category_parent.sql")),
+    (6, include_str!("migration/0006_product_tag.sql")),
+    (7, include_str!("migration/0007_category_trim.sql")),
+    (8, include_str!("migration/0008_product_active.sql")),
+];
+
+
+pub fn migrate(connection: &mut Connection) -> Result<(), DatabaseError> {
+    if !has_schema(connection)? {
+        warn!("Database schema not found, migrations skipped");
+
+        return Ok(());
+    }
+
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version ( version INTEGER NOT NULL )",
+    )?;
+
+    let current_version = get_schema_version(connection)?;
+
+    for &(version, script) in MIGRATIONS {
+        if version > current_version {
+            info!("Applying database migration {}", version);
+
+            connection.execute("BEGIN TRANSACTION")?;
+
+            match apply_migration(connection, version, script) {
+                Ok(()) => connection.execute("COMMIT")?,
+                Err(error) => {
+                    connection.execute("ROLLBACK")?;
+
+                    return Err(error);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_migration(
+    connection: &mut Connection,
+    version: i64,
+    script: &str,
+) -> Result<(), DatabaseError> {
+    connection.execute(script)?;
+
+    let statement = connection.prepare("INSERT INTO schema_version ( version ) VALUES ( ? )")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(version)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn has_schema(connection: &mut Connection) -> Result<bool, DatabaseError> {
+    let mut statement = connection.prepare(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'iteration'",
+    )?;
+
+    if let State::Row = statement.next()? {
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+fn get_schema_version(connection: &mut Connection) -> Result<i64, DatabaseError> {
+    let mut statement = connection.prepare("SELECT MAX(version) FROM schema_version")?;
+
+    if let State::Row = statement.next()? {
+        match statement.read(0)? {
+            Value::Integer(version) => Ok(version),
+            _ => Ok(0),
+        }
+    } else {
+        Ok(0)
+    }
+}
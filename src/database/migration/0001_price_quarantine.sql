@@ -0,0 +1 @@
+ALTER TABLE product_price ADD COLUMN quarantine TEXT NULL ;
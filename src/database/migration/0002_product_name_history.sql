@@ -0,0 +1,17 @@
+CREATE TABLE product_name (
+    id INTEGER PRIMARY KEY,
+    product_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    substitution INTEGER NOT NULL
+);
+
+CREATE INDEX nx_product_name_product_id ON product_name ( product_id ) ;
+
+INSERT INTO product_name ( product_id, name, timestamp, substitution )
+SELECT
+    p.id,
+    p.name,
+    COALESCE(( SELECT MIN(pp.timestamp) FROM product_price AS pp WHERE pp.product_id = p.id ), 0),
+    0
+FROM product AS p ;
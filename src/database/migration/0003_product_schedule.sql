@@ -0,0 +1,7 @@
+CREATE TABLE product_schedule (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    next_run INTEGER NOT NULL
+);
+
+CREATE UNIQUE INDEX nx_product_schedule_url ON product_schedule ( url ) ;
@@ -0,0 +1,10 @@
+CREATE TABLE product_group (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX nx_product_group_name ON product_group ( name ) ;
+
+ALTER TABLE product ADD COLUMN group_id INTEGER NULL ;
+
+CREATE INDEX nx_product_group_id ON product ( group_id ) ;
@@ -0,0 +1,3 @@
+ALTER TABLE category ADD COLUMN parent_id INTEGER NULL ;
+
+CREATE INDEX nx_category_parent_id ON category ( parent_id ) ;
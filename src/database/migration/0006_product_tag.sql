@@ -0,0 +1,16 @@
+CREATE TABLE tag (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX nx_tag_name ON tag ( name ) ;
+
+CREATE TABLE product_tag (
+    id INTEGER PRIMARY KEY,
+    product_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL
+);
+
+CREATE UNIQUE INDEX nx_product_tag_product_id_tag_id ON product_tag ( product_id, tag_id ) ;
+
+CREATE INDEX nx_product_tag_tag_id ON product_tag ( tag_id ) ;
@@ -0,0 +1,25 @@
+CREATE TEMPORARY TABLE category_trim AS
+SELECT
+    c.id AS id,
+    k.id AS target_id
+FROM category AS c
+    INNER JOIN (
+        SELECT TRIM(name) AS name, MIN(id) AS id
+        FROM category
+        GROUP BY TRIM(name)
+    ) AS k ON ( k.name = TRIM(c.name) )
+WHERE c.id <> k.id ;
+
+UPDATE product
+SET category_id = ( SELECT target_id FROM category_trim WHERE id = product.category_id )
+WHERE category_id IN ( SELECT id FROM category_trim ) ;
+
+UPDATE category
+SET parent_id = ( SELECT target_id FROM category_trim WHERE id = category.parent_id )
+WHERE parent_id IN ( SELECT id FROM category_trim ) ;
+
+DELETE FROM category WHERE id IN ( SELECT id FROM category_trim ) ;
+
+UPDATE category SET name = TRIM(name) WHERE name <> TRIM(name) ;
+
+DROP TABLE category_trim ;
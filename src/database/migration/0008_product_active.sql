@@ -0,0 +1 @@
+ALTER TABLE product ADD COLUMN active INTEGER NOT NULL DEFAULT 1 ;
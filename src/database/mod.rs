@@ -1,8 +1,15 @@
 mod entity;
 mod error;
+mod migration;
+mod similarity;
 mod sqlite;
 
+pub use self::entity::Category;
+pub use self::entity::GroupPrice;
 pub use self::entity::IterationPrice;
+pub use self::entity::NameChange;
+pub use self::entity::PriceReading;
+pub use self::entity::PriceRecord;
 pub use self::entity::Product;
 pub use self::entity::ProductPrice;
 pub use self::error::DatabaseError;
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+
+pub fn name_similarity(left: &str, right: &str) -> f64 {
+    let left = bigrams(left);
+    let right = bigrams(right);
+
+    if left.is_empty() && right.is_empty() {
+        return 1.0;
+    }
+
+    let mut right_counts = HashMap::new();
+
+    for bigram in &right {
+        *right_counts.entry(bigram).or_insert(0) += 1;
+    }
+
+    let mut common = 0;
+
+    for bigram in &left {
+        if let Some(count) = right_counts.get_mut(bigram) {
+            if *count > 0 {
+                *count -= 1;
+                common += 1;
+            }
+        }
+    }
+
+    2.0 * common as f64 / (left.len() + right.len()) as f64
+}
+
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let text = text.to_lowercase();
+    let chars: Vec<char> = text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+
+    chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
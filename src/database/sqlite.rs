@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
@@ -11,12 +14,25 @@ use sqlite::State;
 use sqlite::Value;
 use sqlite;
 
+use worker::category_path;
+
+use super::Category;
 use super::DatabaseError;
+use super::GroupPrice;
 use super::IterationPrice;
+use super::migration;
+use super::NameChange;
+use super::PriceReading;
+use super::PriceRecord;
+use super::similarity::name_similarity;
 use super::Product;
 use super::ProductPrice;
 
 
+const SUBSTITUTION_SIMILARITY: f64 = 0.5;
+const CATEGORY_PATH_SEPARATOR: &'static str = " > ";
+
+
 #[derive(Clone)]
 pub struct Database {
     connection: Arc<Mutex<Connection>>,
@@ -52,7 +68,7 @@ impl Database {
             }
         }
 
-        let connection = sqlite::open(&file_path)?;
+        let mut connection = sqlite::open(&file_path)?;
 
         if create {
             info!("Creating tables");
@@ -62,6 +78,8 @@ impl Database {
             info!("All tables created");
         }
 
+        migration::migrate(&mut connection)?;
+
         Ok(Database { connection: Arc::new(Mutex::new(connection)) })
     }
 
@@ -74,19 +92,60 @@ impl Database {
         iteration: i64,
         timestamp: i64,
         price: f64,
-    ) -> Result<(), DatabaseError> {
+        quarantine: Option<&str>,
+        group: Option<&str>,
+    ) -> Result<Option<NameChange>, DatabaseError> {
         let mut connection = self.connection.lock()?;
         let shop_id = shop_id(&mut connection, shop)?;
         let category_id = category_id(&mut connection, category)?;
-        let product_id = product_id(
+        let (product_id, name_change) = product_id(
             &mut connection,
             shop_id,
             category_id,
             product_url,
             product_name,
+            timestamp,
+        )?;
+        let group_id = match group {
+            Some(group) => Some(group_id(&mut connection, group)?),
+            None => None,
+        };
+
+        update_product_category(&mut connection, product_id, category_id)?;
+        update_product_group(&mut connection, product_id, group_id)?;
+
+        save_product_price(
+            &mut connection,
+            product_id,
+            iteration,
+            timestamp,
+            price,
+            quarantine,
         )?;
 
-        save_product_price(&mut connection, product_id, iteration, timestamp, price)?;
+        Ok(name_change)
+    }
+
+    pub fn last_accepted_price(&self, product_url: &str) -> Result<Option<f64>, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        let price = get_last_accepted_price(&mut connection, product_url)?;
+
+        Ok(price)
+    }
+
+    pub fn last_price_reading(
+        &self,
+        product_url: &str,
+    ) -> Result<Option<PriceReading>, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        let reading = get_last_price_reading(&mut connection, product_url)?;
+
+        Ok(reading)
+    }
+
+    pub fn release_quarantine(&self, product_url: &str) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        release_product_quarantine(&mut connection, product_url)?;
 
         Ok(())
     }
@@ -105,9 +164,13 @@ impl Database {
         Ok(())
     }
 
-    pub fn products_by_category(&self, category_id: i64) -> Result<Vec<Product>, DatabaseError> {
+    pub fn products_by_category(
+        &self,
+        category_id: i64,
+        tag: Option<&str>,
+    ) -> Result<Vec<Product>, DatabaseError> {
         let mut connection = self.connection.lock()?;
-        let products = get_products_by_category(&mut connection, category_id)?;
+        let products = get_products_by_category(&mut connection, category_id, tag)?;
 
         Ok(products)
     }
@@ -122,24 +185,157 @@ impl Database {
         Ok(products)
     }
 
-    pub fn product_price_by_iteration(
+    pub fn latest_product_prices(
         &self,
-        iteration_from: i64,
-        iteration_to: i64,
+        tag: Option<&str>,
     ) -> Result<Vec<IterationPrice>, DatabaseError> {
         let mut connection = self.connection.lock()?;
-        let product_prices =
-            get_product_price_by_iteration(&mut connection, iteration_from, iteration_to)?;
+        let product_prices = get_latest_product_prices(&mut connection, tag)?;
 
         Ok(product_prices)
     }
+
+    pub fn save_tags(&self, product_url: &str, tags: &[String]) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock()?;
+
+        if let Some((product_id, _)) = get_product(&mut connection, product_url)? {
+            delete_product_tags(&mut connection, product_id)?;
+
+            for tag in tags {
+                let tag_id = tag_id(&mut connection, tag)?;
+
+                save_product_tag(&mut connection, product_id, tag_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn categories(&self) -> Result<Vec<Category>, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        let categories = get_categories(&mut connection)?;
+
+        Ok(categories)
+    }
+
+    pub fn group_prices(&self) -> Result<Vec<GroupPrice>, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        let group_prices = get_group_prices(&mut connection)?;
+
+        Ok(group_prices)
+    }
+
+    pub fn next_runs(&self) -> Result<HashMap<String, i64>, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        let next_runs = get_next_runs(&mut connection)?;
+
+        Ok(next_runs)
+    }
+
+    pub fn save_next_run(&self, product_url: &str, next_run: i64) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        update_next_run(&mut connection, product_url, next_run)?;
+
+        Ok(())
+    }
+
+    pub fn update_active_products(
+        &self,
+        urls: &HashSet<String>,
+        skipped_shops: &HashSet<String>,
+    ) -> Result<(), DatabaseError> {
+        let mut connection = self.connection.lock()?;
+
+        for (id, shop, url) in get_product_urls(&mut connection)? {
+            if !skipped_shops.contains(&shop) {
+                update_product_active(&mut connection, id, urls.contains(&url))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn price_records(&self) -> Result<Vec<PriceRecord>, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+        let price_records = get_price_records(&mut connection)?;
+
+        Ok(price_records)
+    }
+
+    pub fn import_price(
+        &self,
+        record: &PriceRecord,
+        iteration: i64,
+    ) -> Result<bool, DatabaseError> {
+        let mut connection = self.connection.lock()?;
+
+        if has_product_price(&mut connection, &record.url(), record.timestamp())? {
+            return Ok(false);
+        }
+
+        let shop_id = shop_id(&mut connection, &record.shop())?;
+        let category_id = category_id(&mut connection, &record.category())?;
+        let group_id = match record.group() {
+            Some(group) => Some(group_id(&mut connection, &group)?),
+            None => None,
+        };
+        let product_id = import_product(
+            &mut connection,
+            shop_id,
+            category_id,
+            group_id,
+            &record.url(),
+            &record.name(),
+            record.timestamp(),
+        )?;
+
+        save_product_price(
+            &mut connection,
+            product_id,
+            iteration,
+            record.timestamp(),
+            record.price(),
+            record.quarantine().as_ref().map(|reason| reason.as_str()),
+        )?;
+
+        Ok(true)
+    }
+
+    pub fn merge_duplicates<F>(&self, canonical_url: F) -> Result<usize, DatabaseError>
+    where
+        F: Fn(&str, &str) -> String,
+    {
+        let mut connection = self.connection.lock()?;
+        let mut products = BTreeMap::new();
+
+        for (id, shop, url) in get_product_urls(&mut connection)? {
+            products
+                .entry(canonical_url(&shop, &url))
+                .or_insert_with(Vec::new)
+                .push((id, url));
+        }
+
+        connection.execute("BEGIN TRANSACTION")?;
+
+        match merge_products(&mut connection, &products) {
+            Ok(merged) => {
+                connection.execute("COMMIT")?;
+
+                Ok(merged)
+            }
+            Err(error) => {
+                connection.execute("ROLLBACK")?;
+
+                Err(error)
+            }
+        }
+    }
 }
 
 
-fn get_product_price_by_iteration(
+fn get_latest_product_prices(
     connection: &mut Connection,
-    iteration_from: i64,
-    iteration_to: i64,
+    tag: Option<&str>,
 ) -> Result<Vec<IterationPrice>, DatabaseError> {
     let mut statement = connection.prepare(
         r#"
@@ -152,14 +348,26 @@ SELECT
     pp.price,
     pp.timestamp
 FROM product_price AS pp
+    INNER JOIN (
+        SELECT MAX(id) AS id
+        FROM product_price
+        WHERE quarantine IS NULL
+        GROUP BY product_id
+    ) AS lp ON ( lp.id = pp.id )
     INNER JOIN product AS p ON ( p.id = pp.product_id )
     INNER JOIN category AS c ON ( c.id = p.category_id )
     INNER JOIN shop as s ON ( s.id = p.shop_id )
-WHERE pp.iteration BETWEEN ? AND ?
+WHERE p.active = 1
+    AND ( ? IS NULL OR p.id IN (
+        SELECT pt.product_id
+        FROM product_tag AS pt
+            INNER JOIN tag AS t ON ( t.id = pt.tag_id )
+        WHERE t.name = ?
+    ) )
 "#,
     )?;
-    statement.bind(1, iteration_from)?;
-    statement.bind(2, iteration_to)?;
+    statement.bind(1, &optional_text(tag))?;
+    statement.bind(2, &optional_text(tag))?;
 
     let mut result = Vec::new();
 
@@ -191,7 +399,12 @@ fn get_product_prices_by_product(
     product_id: i64,
 ) -> Result<Vec<ProductPrice>, DatabaseError> {
     let mut statement = connection.prepare(
-        "SELECT iteration, timestamp, price FROM product_price WHERE product_id = ?",
+        r#"
+SELECT iteration, timestamp, price
+FROM product_price
+WHERE product_id = ?
+    AND quarantine IS NULL
+"#,
     )?;
     statement.bind(1, product_id)?;
 
@@ -208,14 +421,111 @@ fn get_product_prices_by_product(
     Ok(result)
 }
 
+fn get_last_accepted_price(
+    connection: &mut Connection,
+    product_url: &str,
+) -> Result<Option<f64>, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT pp.price
+FROM product_price AS pp
+    INNER JOIN product AS p ON ( p.id = pp.product_id )
+WHERE p.url = ?
+    AND pp.quarantine IS NULL
+ORDER BY pp.id DESC
+LIMIT 1
+"#,
+    )?;
+    statement.bind(1, product_url)?;
+
+    if let State::Row = statement.next()? {
+        let price = statement.read(0)?;
+
+        Ok(Some(price))
+    } else {
+        Ok(None)
+    }
+}
+
+fn get_last_price_reading(
+    connection: &mut Connection,
+    product_url: &str,
+) -> Result<Option<PriceReading>, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT pp.price, pp.quarantine
+FROM product_price AS pp
+    INNER JOIN product AS p ON ( p.id = pp.product_id )
+WHERE p.url = ?
+ORDER BY pp.id DESC
+LIMIT 1
+"#,
+    )?;
+    statement.bind(1, product_url)?;
+
+    if let State::Row = statement.next()? {
+        let price = statement.read(0)?;
+        let quarantine = match statement.read(1)? {
+            Value::String(reason) => Some(reason),
+            _ => None,
+        };
+
+        Ok(Some(PriceReading::new(price, quarantine)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn release_product_quarantine(
+    connection: &mut Connection,
+    product_url: &str,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        r#"
+UPDATE product_price SET quarantine = NULL
+WHERE id = (
+    SELECT MAX(pp.id)
+    FROM product_price AS pp
+        INNER JOIN product AS p ON ( p.id = pp.product_id )
+    WHERE p.url = ?
+)
+"#,
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::String(product_url.into())])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
 fn get_products_by_category(
     connection: &mut Connection,
     category_id: i64,
+    tag: Option<&str>,
 ) -> Result<Vec<Product>, DatabaseError> {
     let mut statement = connection.prepare(
-        "SELECT id, name FROM product WHERE category_id = ?",
+        r#"
+WITH RECURSIVE subcategory ( id ) AS (
+    SELECT ?
+    UNION ALL
+    SELECT c.id
+    FROM category AS c
+        INNER JOIN subcategory AS sc ON ( c.parent_id = sc.id )
+)
+SELECT id, name
+FROM product
+WHERE category_id IN ( SELECT id FROM subcategory )
+    AND ( ? IS NULL OR id IN (
+        SELECT pt.product_id
+        FROM product_tag AS pt
+            INNER JOIN tag AS t ON ( t.id = pt.tag_id )
+        WHERE t.name = ?
+    ) )
+"#,
     )?;
     statement.bind(1, category_id)?;
+    statement.bind(2, &optional_text(tag))?;
+    statement.bind(3, &optional_text(tag))?;
 
     let mut result = Vec::new();
 
@@ -229,6 +539,104 @@ fn get_products_by_category(
     Ok(result)
 }
 
+fn get_categories(connection: &mut Connection) -> Result<Vec<Category>, DatabaseError> {
+    let mut statement = connection.prepare("SELECT id, name, parent_id FROM category")?;
+    let mut result = Vec::new();
+
+    while let State::Row = statement.next()? {
+        let id = statement.read(0)?;
+        let name = statement.read(1)?;
+        let parent_id = match statement.read(2)? {
+            Value::Integer(parent_id) => Some(parent_id),
+            _ => None,
+        };
+
+        result.push(Category::new(id, name, parent_id));
+    }
+
+    Ok(result)
+}
+
+fn get_group_prices(connection: &mut Connection) -> Result<Vec<GroupPrice>, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT
+    g.id,
+    g.name,
+    p.name,
+    p.url,
+    s.name,
+    pp.price,
+    pp.timestamp
+FROM product_price AS pp
+    INNER JOIN (
+        SELECT MAX(id) AS id
+        FROM product_price
+        WHERE quarantine IS NULL
+        GROUP BY product_id
+    ) AS lp ON ( lp.id = pp.id )
+    INNER JOIN product AS p ON ( p.id = pp.product_id )
+    INNER JOIN product_group AS g ON ( g.id = p.group_id )
+    INNER JOIN shop as s ON ( s.id = p.shop_id )
+WHERE p.active = 1
+ORDER BY g.name, pp.price
+"#,
+    )?;
+
+    let mut result = Vec::new();
+
+    while let State::Row = statement.next()? {
+        let group_id = statement.read(0)?;
+        let group = statement.read(1)?;
+        let product = statement.read(2)?;
+        let url = statement.read(3)?;
+        let shop = statement.read(4)?;
+        let price = statement.read(5)?;
+        let timestamp = statement.read(6)?;
+
+        result.push(GroupPrice::new(
+            group_id,
+            group,
+            product,
+            url,
+            shop,
+            price,
+            timestamp,
+        ));
+    }
+
+    Ok(result)
+}
+
+fn get_next_runs(connection: &mut Connection) -> Result<HashMap<String, i64>, DatabaseError> {
+    let mut statement = connection.prepare("SELECT url, next_run FROM product_schedule")?;
+    let mut result = HashMap::new();
+
+    while let State::Row = statement.next()? {
+        let url = statement.read(0)?;
+        let next_run = statement.read(1)?;
+
+        result.insert(url, next_run);
+    }
+
+    Ok(result)
+}
+
+fn update_next_run(
+    connection: &mut Connection,
+    product_url: &str,
+    next_run: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        "INSERT OR REPLACE INTO product_schedule ( url, next_run ) VALUES ( ?, ? )",
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::String(product_url.into()), Value::Integer(next_run)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
 fn get_iteration(connection: &mut Connection) -> Result<Option<i64>, DatabaseError> {
     let mut statement = connection.prepare(
         "SELECT iteration FROM iteration LIMIT 1",
@@ -258,13 +666,18 @@ fn save_product_price(
     iteration: i64,
     timestamp: i64,
     price: f64,
+    quarantine: Option<&str>,
 ) -> Result<(), DatabaseError> {
     let statement = connection.prepare(
         r#"
-INSERT INTO product_price ( product_id, iteration, timestamp, price )
-VALUES ( ?, ?, ?, ? )
+INSERT INTO product_price ( product_id, iteration, timestamp, price, quarantine )
+VALUES ( ?, ?, ?, ?, ? )
 "#,
     )?;
+    let quarantine = match quarantine {
+        Some(reason) => Value::String(reason.into()),
+        None => Value::Null,
+    };
     let mut cursor = statement.cursor();
     cursor.bind(
         &[
@@ -272,6 +685,7 @@ VALUES ( ?, ?, ?, ? )
             Value::Integer(iteration),
             Value::Integer(timestamp),
             Value::Float(price),
+            quarantine,
         ],
     )?;
     cursor.next()?;
@@ -280,16 +694,29 @@ VALUES ( ?, ?, ?, ? )
 }
 
 fn category_id(connection: &mut Connection, name: &str) -> Result<i64, DatabaseError> {
-    let result;
+    let mut parts = category_path(name);
+    let mut path = Vec::new();
+    let mut result = None;
 
-    if let Some(id) = get_category_id(connection, name)? {
-        result = id
-    } else {
-        save_category(connection, name)?;
-        result = last_inserted_id(connection)?;
+    if parts.is_empty() {
+        parts.push(name);
     }
 
-    Ok(result)
+    for part in parts {
+        path.push(part);
+
+        let path_name = path.join(CATEGORY_PATH_SEPARATOR);
+        let id = if let Some(id) = get_category_id(connection, &path_name)? {
+            id
+        } else {
+            save_category(connection, &path_name, result)?;
+            last_inserted_id(connection)?
+        };
+
+        result = Some(id);
+    }
+
+    result.ok_or_else(DatabaseError::no_data)
 }
 
 fn shop_id(connection: &mut Connection, name: &str) -> Result<i64, DatabaseError> {
@@ -311,39 +738,177 @@ pub fn product_id(
     category_id: i64,
     url: &str,
     name: &str,
-) -> Result<i64, DatabaseError> {
+    timestamp: i64,
+) -> Result<(i64, Option<NameChange>), DatabaseError> {
     let result;
+    let mut name_change = None;
+
+    if let Some((id, old_name)) = get_product(connection, url)? {
+        if old_name != name {
+            let substitution = name_similarity(&old_name, name) < SUBSTITUTION_SIMILARITY;
+
+            update_product_name(connection, id, name)?;
+            save_product_name(connection, id, name, timestamp, substitution)?;
+
+            name_change = Some(NameChange::new(old_name, name.into(), substitution));
+        }
 
-    if let Some(id) = get_product_id(connection, url)? {
         result = id
     } else {
         save_product(connection, shop_id, category_id, url, name)?;
         result = last_inserted_id(connection)?;
+        save_product_name(connection, result, name, timestamp, false)?;
     }
 
-    Ok(result)
+    Ok((result, name_change))
 }
 
-fn get_product_id(connection: &mut Connection, url: &str) -> Result<Option<i64>, DatabaseError> {
-    let mut statement = connection.prepare("SELECT id FROM product WHERE url = ?")?;
-    statement.bind(1, url)?;
+fn import_product(
+    connection: &mut Connection,
+    shop_id: i64,
+    category_id: i64,
+    group_id: Option<i64>,
+    url: &str,
+    name: &str,
+    timestamp: i64,
+) -> Result<i64, DatabaseError> {
+    let product_id = match get_product(connection, url)? {
+        Some((id, _)) => id,
+        None => {
+            save_product(connection, shop_id, category_id, url, name)?;
+            last_inserted_id(connection)?
+        }
+    };
+
+    match get_last_product_name(connection, product_id)? {
+        Some((_, last_timestamp)) if last_timestamp >= timestamp => {
+            if !has_product_name(connection, product_id, name)? {
+                save_product_name(connection, product_id, name, timestamp, false)?;
+            }
+        }
+        last_name => {
+            if last_name.map_or(true, |(last_name, _)| last_name != name) {
+                update_product_name(connection, product_id, name)?;
+                save_product_name(connection, product_id, name, timestamp, false)?;
+            }
+
+            update_product_category(connection, product_id, category_id)?;
+            update_product_group(connection, product_id, group_id)?;
+        }
+    }
+
+    Ok(product_id)
+}
+
+fn get_last_product_name(
+    connection: &mut Connection,
+    product_id: i64,
+) -> Result<Option<(String, i64)>, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT name, timestamp
+FROM product_name
+WHERE product_id = ?
+ORDER BY timestamp DESC, id DESC
+LIMIT 1
+"#,
+    )?;
+    statement.bind(1, product_id)?;
 
     if let State::Row = statement.next()? {
-        let id = statement.read(0)?;
+        let name = statement.read(0)?;
+        let timestamp = statement.read(1)?;
 
-        Ok(Some(id))
+        Ok(Some((name, timestamp)))
     } else {
         Ok(None)
     }
 }
 
-fn save_product(
+fn has_product_name(
     connection: &mut Connection,
-    shop_id: i64,
-    category_id: i64,
-    url: &str,
+    product_id: i64,
     name: &str,
-) -> Result<(), DatabaseError> {
+) -> Result<bool, DatabaseError> {
+    let mut statement = connection.prepare(
+        "SELECT id FROM product_name WHERE product_id = ? AND name = ? LIMIT 1",
+    )?;
+    statement.bind(1, product_id)?;
+    statement.bind(2, name)?;
+
+    if let State::Row = statement.next()? {
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+fn get_product(
+    connection: &mut Connection,
+    url: &str,
+) -> Result<Option<(i64, String)>, DatabaseError> {
+    let mut statement = connection.prepare("SELECT id, name FROM product WHERE url = ?")?;
+    statement.bind(1, url)?;
+
+    if let State::Row = statement.next()? {
+        let id = statement.read(0)?;
+        let name = statement.read(1)?;
+
+        Ok(Some((id, name)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn update_product_name(
+    connection: &mut Connection,
+    product_id: i64,
+    name: &str,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("UPDATE product SET name = ? WHERE id = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(
+        &[Value::String(name.into()), Value::Integer(product_id)],
+    )?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn save_product_name(
+    connection: &mut Connection,
+    product_id: i64,
+    name: &str,
+    timestamp: i64,
+    substitution: bool,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        r#"
+INSERT INTO product_name ( product_id, name, timestamp, substitution )
+VALUES ( ?, ?, ?, ? )
+"#,
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(
+        &[
+            Value::Integer(product_id),
+            Value::String(name.into()),
+            Value::Integer(timestamp),
+            Value::Integer(substitution as i64),
+        ],
+    )?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn save_product(
+    connection: &mut Connection,
+    shop_id: i64,
+    category_id: i64,
+    url: &str,
+    name: &str,
+) -> Result<(), DatabaseError> {
     let statement = connection.prepare(
         "INSERT INTO product ( shop_id, category_id, url, name ) VALUES ( ?, ?, ?, ? )",
     )?;
@@ -361,6 +926,147 @@ fn save_product(
     Ok(())
 }
 
+fn delete_product_tags(connection: &mut Connection, product_id: i64) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("DELETE FROM product_tag WHERE product_id = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(product_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn save_product_tag(
+    connection: &mut Connection,
+    product_id: i64,
+    tag_id: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        "INSERT OR IGNORE INTO product_tag ( product_id, tag_id ) VALUES ( ?, ? )",
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(product_id), Value::Integer(tag_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn tag_id(connection: &mut Connection, name: &str) -> Result<i64, DatabaseError> {
+    let result;
+
+    if let Some(id) = get_tag_id(connection, name)? {
+        result = id
+    } else {
+        save_tag(connection, name)?;
+        result = last_inserted_id(connection)?;
+    }
+
+    Ok(result)
+}
+
+fn get_tag_id(connection: &mut Connection, name: &str) -> Result<Option<i64>, DatabaseError> {
+    let mut statement = connection.prepare("SELECT id FROM tag WHERE name = ?")?;
+    statement.bind(1, name)?;
+
+    if let State::Row = statement.next()? {
+        let id = statement.read(0)?;
+
+        Ok(Some(id))
+    } else {
+        Ok(None)
+    }
+}
+
+fn save_tag(connection: &mut Connection, name: &str) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("INSERT INTO tag ( name ) VALUES ( ? )")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::String(name.into())])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn update_product_category(
+    connection: &mut Connection,
+    product_id: i64,
+    category_id: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("UPDATE product SET category_id = ? WHERE id = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(
+        &[Value::Integer(category_id), Value::Integer(product_id)],
+    )?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn update_product_active(
+    connection: &mut Connection,
+    product_id: i64,
+    active: bool,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("UPDATE product SET active = ? WHERE id = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(
+        &[Value::Integer(active as i64), Value::Integer(product_id)],
+    )?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn update_product_group(
+    connection: &mut Connection,
+    product_id: i64,
+    group_id: Option<i64>,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("UPDATE product SET group_id = ? WHERE id = ?")?;
+    let group_id = match group_id {
+        Some(group_id) => Value::Integer(group_id),
+        None => Value::Null,
+    };
+    let mut cursor = statement.cursor();
+    cursor.bind(&[group_id, Value::Integer(product_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn group_id(connection: &mut Connection, name: &str) -> Result<i64, DatabaseError> {
+    let result;
+
+    if let Some(id) = get_group_id(connection, name)? {
+        result = id
+    } else {
+        save_group(connection, name)?;
+        result = last_inserted_id(connection)?;
+    }
+
+    Ok(result)
+}
+
+fn get_group_id(connection: &mut Connection, name: &str) -> Result<Option<i64>, DatabaseError> {
+    let mut statement = connection.prepare("SELECT id FROM product_group WHERE name = ?")?;
+    statement.bind(1, name)?;
+
+    if let State::Row = statement.next()? {
+        let id = statement.read(0)?;
+
+        Ok(Some(id))
+    } else {
+        Ok(None)
+    }
+}
+
+fn save_group(connection: &mut Connection, name: &str) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("INSERT INTO product_group ( name ) VALUES ( ? )")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::String(name.into())])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
 fn get_shop_id(connection: &mut Connection, name: &str) -> Result<Option<i64>, DatabaseError> {
     let mut statement = connection.prepare("SELECT id FROM shop WHERE name = ?")?;
     statement.bind(1, name)?;
@@ -396,12 +1102,303 @@ fn get_category_id(connection: &mut Connection, name: &str) -> Result<Option<i64
     }
 }
 
-fn save_category(connection: &mut Connection, name: &str) -> Result<(), DatabaseError> {
+fn save_category(
+    connection: &mut Connection,
+    name: &str,
+    parent_id: Option<i64>,
+) -> Result<(), DatabaseError> {
     let statement = connection.prepare(
-        "INSERT INTO category ( name ) VALUES ( ? )",
+        "INSERT INTO category ( name, parent_id ) VALUES ( ?, ? )",
     )?;
+    let parent_id = match parent_id {
+        Some(parent_id) => Value::Integer(parent_id),
+        None => Value::Null,
+    };
     let mut cursor = statement.cursor();
-    cursor.bind(&[Value::String(name.into())])?;
+    cursor.bind(&[Value::String(name.into()), parent_id])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn get_price_records(connection: &mut Connection) -> Result<Vec<PriceRecord>, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT
+    s.name,
+    c.name,
+    p.url,
+    p.name,
+    g.name,
+    pp.iteration,
+    pp.timestamp,
+    pp.price,
+    pp.quarantine
+FROM product_price AS pp
+    INNER JOIN product AS p ON ( p.id = pp.product_id )
+    INNER JOIN shop AS s ON ( s.id = p.shop_id )
+    INNER JOIN category AS c ON ( c.id = p.category_id )
+    LEFT JOIN product_group AS g ON ( g.id = p.group_id )
+ORDER BY pp.id
+"#,
+    )?;
+
+    let mut result = Vec::new();
+
+    while let State::Row = statement.next()? {
+        let shop = statement.read(0)?;
+        let category = statement.read(1)?;
+        let url = statement.read(2)?;
+        let name = statement.read(3)?;
+        let group = match statement.read(4)? {
+            Value::String(group) => Some(group),
+            _ => None,
+        };
+        let iteration = statement.read(5)?;
+        let timestamp = statement.read(6)?;
+        let price = statement.read(7)?;
+        let quarantine = match statement.read(8)? {
+            Value::String(reason) => Some(reason),
+            _ => None,
+        };
+
+        result.push(PriceRecord::new(
+            shop,
+            category,
+            url,
+            name,
+            group,
+            iteration,
+            timestamp,
+            price,
+            quarantine,
+        ));
+    }
+
+    Ok(result)
+}
+
+fn has_product_price(
+    connection: &mut Connection,
+    product_url: &str,
+    timestamp: i64,
+) -> Result<bool, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT pp.id
+FROM product_price AS pp
+    INNER JOIN product AS p ON ( p.id = pp.product_id )
+WHERE p.url = ?
+    AND pp.timestamp = ?
+LIMIT 1
+"#,
+    )?;
+    statement.bind(1, product_url)?;
+    statement.bind(2, timestamp)?;
+
+    if let State::Row = statement.next()? {
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+fn get_product_urls(
+    connection: &mut Connection,
+) -> Result<Vec<(i64, String, String)>, DatabaseError> {
+    let mut statement = connection.prepare(
+        r#"
+SELECT p.id, s.name, p.url
+FROM product AS p
+    INNER JOIN shop AS s ON ( s.id = p.shop_id )
+ORDER BY p.id
+"#,
+    )?;
+    let mut result = Vec::new();
+
+    while let State::Row = statement.next()? {
+        let id = statement.read(0)?;
+        let shop = statement.read(1)?;
+        let url = statement.read(2)?;
+
+        result.push((id, shop, url));
+    }
+
+    Ok(result)
+}
+
+fn merge_products(
+    connection: &mut Connection,
+    products: &BTreeMap<String, Vec<(i64, String)>>,
+) -> Result<usize, DatabaseError> {
+    let mut merged = 0;
+
+    for (url, duplicates) in products {
+        let product_id = duplicates[0].0;
+
+        if duplicates.len() == 1 && duplicates[0].1 == *url {
+            continue;
+        }
+
+        let mut latest = (product_id, last_price_timestamp(connection, product_id)?);
+
+        for &(duplicate_id, _) in &duplicates[1..] {
+            let timestamp = last_price_timestamp(connection, duplicate_id)?;
+
+            if timestamp > latest.1 {
+                latest = (duplicate_id, timestamp);
+            }
+        }
+
+        if latest.0 != product_id {
+            copy_product_details(connection, latest.0, product_id)?;
+        }
+
+        for &(duplicate_id, _) in &duplicates[1..] {
+            info!("Merging product #{} into #{} as `{}`", duplicate_id, product_id, url);
+
+            delete_overlapping_prices(connection, duplicate_id, product_id)?;
+            move_product_rows(connection, "product_price", duplicate_id, product_id)?;
+            move_product_rows(connection, "product_name", duplicate_id, product_id)?;
+            move_product_tags(connection, duplicate_id, product_id)?;
+            delete_product(connection, duplicate_id)?;
+
+            merged += 1;
+        }
+
+        for &(_, ref old_url) in duplicates.iter().filter(|&&(_, ref old_url)| old_url != url) {
+            delete_next_run(connection, old_url)?;
+        }
+
+        update_product_url(connection, product_id, url)?;
+    }
+
+    Ok(merged)
+}
+
+fn last_price_timestamp(
+    connection: &mut Connection,
+    product_id: i64,
+) -> Result<Option<i64>, DatabaseError> {
+    let mut statement = connection.prepare(
+        "SELECT timestamp FROM product_price WHERE product_id = ? ORDER BY timestamp DESC LIMIT 1",
+    )?;
+    statement.bind(1, product_id)?;
+
+    if let State::Row = statement.next()? {
+        Ok(Some(statement.read(0)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn copy_product_details(
+    connection: &mut Connection,
+    from_id: i64,
+    to_id: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        r#"
+UPDATE product
+SET category_id = ( SELECT category_id FROM product WHERE id = ? ),
+    group_id = ( SELECT group_id FROM product WHERE id = ? ),
+    active = ( SELECT active FROM product WHERE id = ? )
+WHERE id = ?
+"#,
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(
+        &[
+            Value::Integer(from_id),
+            Value::Integer(from_id),
+            Value::Integer(from_id),
+            Value::Integer(to_id),
+        ],
+    )?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn delete_overlapping_prices(
+    connection: &mut Connection,
+    from_id: i64,
+    to_id: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        r#"
+DELETE FROM product_price
+WHERE product_id = ?
+    AND iteration IN ( SELECT iteration FROM product_price WHERE product_id = ? )
+"#,
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(from_id), Value::Integer(to_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn move_product_rows(
+    connection: &mut Connection,
+    table: &str,
+    from_id: i64,
+    to_id: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        format!("UPDATE {} SET product_id = ? WHERE product_id = ?", table),
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(to_id), Value::Integer(from_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn move_product_tags(
+    connection: &mut Connection,
+    from_id: i64,
+    to_id: i64,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare(
+        r#"
+INSERT OR IGNORE INTO product_tag ( product_id, tag_id )
+SELECT ?, tag_id FROM product_tag WHERE product_id = ?
+"#,
+    )?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(to_id), Value::Integer(from_id)])?;
+    cursor.next()?;
+
+    delete_product_tags(connection, from_id)
+}
+
+fn delete_product(connection: &mut Connection, product_id: i64) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("DELETE FROM product WHERE id = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::Integer(product_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn update_product_url(
+    connection: &mut Connection,
+    product_id: i64,
+    url: &str,
+) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("UPDATE product SET url = ? WHERE id = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::String(url.into()), Value::Integer(product_id)])?;
+    cursor.next()?;
+
+    Ok(())
+}
+
+fn delete_next_run(connection: &mut Connection, product_url: &str) -> Result<(), DatabaseError> {
+    let statement = connection.prepare("DELETE FROM product_schedule WHERE url = ?")?;
+    let mut cursor = statement.cursor();
+    cursor.bind(&[Value::String(product_url.into())])?;
     cursor.next()?;
 
     Ok(())
@@ -416,3 +1413,10 @@ fn last_inserted_id(connection: &mut Connection) -> Result<i64, DatabaseError> {
         Err(DatabaseError::no_data())
     }
 }
+
+fn optional_text(value: Option<&str>) -> Value {
+    match value {
+        Some(value) => Value::String(value.into()),
+        None => Value::Null,
+    }
+}
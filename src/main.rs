@@ -5,35 +5,70 @@ extern crate log;
 extern crate serde_derive;
 
 extern crate argparse;
+extern crate chrono;
+extern crate cron;
+extern crate ctrlc;
 extern crate env_logger;
+extern crate flate2;
 extern crate futures;
+extern crate glob;
 extern crate hyper;
 extern crate hyper_tls;
 extern crate iron;
 extern crate kuchiki;
 extern crate mount;
 extern crate native_tls;
+extern crate notify;
+extern crate regex;
+extern crate rhai;
 extern crate router;
+extern crate serde;
+extern crate serde_ignored;
 extern crate serde_json;
+extern crate serde_yaml;
 extern crate sqlite;
 extern crate staticfile;
 extern crate tendril;
 extern crate time;
 extern crate tokio_core;
 extern crate toml;
+extern crate url;
 extern crate urlencoded;
+extern crate xml;
 
 mod backend;
 mod database;
 mod logger;
 mod settings;
+mod shutdown;
 mod worker;
 
+use std::fs::File;
+use std::io::stdout;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
 use backend::start_backend;
 use database::Database;
+use database::PriceRecord;
 use logger::UnwrapLog;
+use settings::CheckSettings;
+use settings::Command;
+use settings::DatabaseAction;
+use settings::DatabaseSettings;
+use settings::ExportSettings;
+use settings::ImportSettings;
 use settings::Settings;
+use settings::ValidateSettings;
+use shutdown::Shutdown;
+use worker::check_product;
+use worker::crawl_once;
+use worker::load_config;
+use worker::LoaderMode;
+use worker::read_config;
 use worker::start_crawler;
+use worker::validate_config;
 
 
 fn main() {
@@ -41,18 +76,236 @@ fn main() {
         panic!("Failed to initialize logger: {}", err);
     }
 
-    let settings = Settings::from_args();
+    match Command::from_args() {
+        Command::Serve(settings) => serve(settings),
+        Command::Crawl(settings) => crawl(settings),
+        Command::Database(settings) => database(settings),
+        Command::Export(settings) => export(settings),
+        Command::Import(settings) => import(settings),
+        Command::Check(settings) => check(settings),
+        Command::Validate(settings) => validate(settings),
+    }
+}
+
+
+fn serve(settings: Settings) {
+    if settings.print_settings() {
+        settings.print();
+
+        return;
+    }
+
     let database = Database::connect(
         settings.database_path(),
         settings.create_database(),
         settings.force(),
     ).unwrap_log("Can't initialize database connection");
+    let shutdown = Shutdown::new();
+
+    shutdown.install().unwrap_log("Can't install termination signal handler");
+
+    let crawler = if settings.disable_crawler() {
+        None
+    } else {
+        let crawler = start_crawler(
+            database.clone(),
+            settings.config_path(),
+            settings.period(),
+            loader_mode(&settings),
+            shutdown.clone(),
+        ).unwrap_log("Can't start background loader thread");
+
+        Some(crawler)
+    };
+    let control = crawler.as_ref().map(|&(ref control, _)| control.clone());
+
+    let _listening = start_backend(
+        database,
+        control,
+        shutdown.clone(),
+        &settings.bind_address(),
+        settings.bind_port(),
+    ).unwrap_log("Can't start back-end server");
+
+    shutdown.wait();
+
+    if let Some((control, handle)) = crawler {
+        control.stop();
+
+        if handle.join().is_err() {
+            error!("Background loader thread failed");
+        }
+    }
+
+    info!("Shutdown complete");
+
+    process::exit(0);
+}
+
+
+fn crawl(settings: Settings) {
+    if settings.print_settings() {
+        settings.print();
 
-    if !settings.disable_crawler() {
-        start_crawler(database.clone(), settings.config_path(), settings.period())
-            .unwrap_log("Can't start background loader thread");
+        return;
     }
 
-    start_backend(database, &settings.bind_address(), settings.bind_port())
-        .unwrap_log("Can't start back-end server");
+    let database = Database::connect(
+        settings.database_path(),
+        settings.create_database(),
+        settings.force(),
+    ).unwrap_log("Can't initialize database connection");
+    let mode = loader_mode(&settings);
+    let shutdown = Shutdown::new();
+
+    shutdown.install().unwrap_log("Can't install termination signal handler");
+
+    let result =
+        crawl_once(&database, settings.config_path(), settings.period(), mode, shutdown.clone());
+
+    if let Err(error) = result {
+        error!("Crawl failed: {}", error);
+
+        process::exit(1);
+    }
+
+    if shutdown.is_requested() {
+        info!("Shutdown complete");
+    }
+}
+
+
+fn database(settings: DatabaseSettings) {
+    match settings.action() {
+        DatabaseAction::Init => {
+            Database::connect(settings.database_path(), true, settings.force())
+                .unwrap_log("Can't create database");
+
+            info!("Database `{}` created", settings.database_path());
+        }
+        DatabaseAction::Migrate => {
+            open_database(&settings.database_path());
+
+            info!("Database `{}` schema is up to date", settings.database_path());
+        }
+        DatabaseAction::Merge => {
+            let database = open_database(&settings.database_path());
+            let config = read_config(settings.config_path()).unwrap_log("Can't read configuration");
+            let merged = database
+                .merge_duplicates(|shop, url| config.canonical_url(shop, url))
+                .unwrap_log("Can't merge duplicate products");
+
+            info!("Merged {} duplicate products in `{}`", merged, settings.database_path());
+        }
+    }
+}
+
+
+fn export(settings: ExportSettings) {
+    let database = open_database(&settings.database_path());
+    let records = database.price_records().unwrap_log("Can't read prices");
+    let mut output: Box<Write> = match settings.output_path() {
+        Some(path) => Box::new(File::create(path).unwrap_log("Can't create output file")),
+        None => Box::new(stdout()),
+    };
+
+    serde_json::to_writer_pretty(&mut output, &records).unwrap_log("Can't write prices");
+    output.flush().unwrap_log("Can't write prices");
+
+    info!("Exported {} prices", records.len());
+}
+
+
+fn import(settings: ImportSettings) {
+    let database = open_database(&settings.database_path());
+    let input = File::open(settings.input_path()).unwrap_log("Can't open input file");
+    let records: Vec<PriceRecord> =
+        serde_json::from_reader(input).unwrap_log("Can't parse input file");
+    let iteration = database.iteration().unwrap_log("Can't read current iteration");
+    let first_iteration = records.iter().map(|record| record.iteration()).min().unwrap_or(0);
+    let offset = iteration.map_or(0, |iteration| iteration + 1) - first_iteration;
+    let mut imported = 0;
+    let mut last_iteration = None;
+
+    for record in &records {
+        let iteration = record.iteration() + offset;
+
+        if database.import_price(record, iteration).unwrap_log("Can't import price") {
+            imported += 1;
+            last_iteration = last_iteration.max(Some(iteration));
+        }
+    }
+
+    if let Some(last_iteration) = last_iteration {
+        database.save_iteration(last_iteration).unwrap_log("Can't write iteration");
+    }
+
+    info!("Imported {} of {} prices", imported, records.len());
+}
+
+
+fn open_database(path: &str) -> Database {
+    if !Path::new(path).exists() {
+        error!("Database `{}` does not exist, use `db init`", path);
+
+        process::exit(1);
+    }
+
+    Database::connect(path, false, false).unwrap_log("Can't initialize database connection")
+}
+
+
+fn loader_mode(settings: &Settings) -> LoaderMode {
+    match (settings.fixtures_path(), settings.record_path(), settings.replay_path()) {
+        (None, None, None) => LoaderMode::Network,
+        (Some(directory), None, None) => LoaderMode::Fixtures { directory: directory.into() },
+        (None, Some(directory), None) => LoaderMode::Record { directory: directory.into() },
+        (None, None, Some(directory)) => LoaderMode::Replay { directory: directory.into() },
+        _ => {
+            error!("Options --fixtures, --record and --replay can not be used together");
+
+            process::exit(1);
+        }
+    }
+}
+
+
+fn check(settings: CheckSettings) {
+    let result = check_product(settings.config_path(), &settings.shop_name(), &settings.url());
+
+    if let Err(error) = result {
+        error!("Check failed: {}", error);
+
+        process::exit(1);
+    }
+}
+
+
+fn validate(settings: ValidateSettings) {
+    let config = match load_config(settings.config_path()) {
+        Ok(config) => config,
+        Err(error) => {
+            println!("error: {}", error);
+
+            process::exit(1);
+        }
+    };
+    let problems = validate_config(&config);
+    let mut errors = 0;
+
+    for problem in &problems {
+        if problem.is_warning() {
+            println!("warning: {}", problem);
+        } else {
+            println!("error: {}", problem);
+
+            errors += 1;
+        }
+    }
+
+    println!("{} errors, {} warnings", errors, problems.len() - errors);
+
+    if errors > 0 {
+        process::exit(1);
+    }
 }
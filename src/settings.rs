@@ -1,7 +1,43 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::io::stderr;
+use std::io::stdout;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
 use argparse::ArgumentParser;
+use argparse::Store;
 use argparse::StoreOption;
 use argparse::StoreTrue;
 
+use worker::read_settings;
+use worker::SettingsConfig;
+
+
+const ENV_PREFIX: &'static str = "BEST_PRICE_";
+const DEFAULT_CONFIG_PATH: &'static str = "config.toml";
+const DEFAULT_DATABASE_PATH: &'static str = "local.sqlite";
+const SERVE_DESCRIPTION: &'static str =
+    "Online shop parsing tool. Commands: serve (default), crawl, db init, db migrate, db merge, \
+     export, import, check and validate.";
+const CRAWL_DESCRIPTION: &'static str = "Update prices of scheduled products once and exit.";
+
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    Serve(Settings),
+    Crawl(Settings),
+    Database(DatabaseSettings),
+    Export(ExportSettings),
+    Import(ImportSettings),
+    Check(CheckSettings),
+    Validate(ValidateSettings),
+}
+
 
 #[derive(Debug, Clone)]
 pub struct Settings {
@@ -13,11 +49,97 @@ pub struct Settings {
     create_database: bool,
     force: bool,
     disable_crawler: bool,
+    fixtures_path: Option<String>,
+    record_path: Option<String>,
+    replay_path: Option<String>,
+    print_settings: bool,
+    sources: BTreeMap<&'static str, SettingSource>,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum SettingSource {
+    Default,
+    File { path: String },
+    Environment { variable: String },
+    CommandLine,
+}
+
+
+#[derive(Debug, Clone, Copy)]
+pub enum DatabaseAction {
+    Init,
+    Migrate,
+    Merge,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct DatabaseSettings {
+    action: DatabaseAction,
+    database_path: String,
+    config_path: String,
+    force: bool,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ExportSettings {
+    database_path: String,
+    output_path: Option<String>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ImportSettings {
+    database_path: String,
+    input_path: String,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct CheckSettings {
+    config_path: String,
+    shop_name: String,
+    url: String,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ValidateSettings {
+    config_path: String,
+}
+
+
+impl Command {
+    pub fn from_args() -> Command {
+        let mut args: Vec<String> = env::args().collect();
+
+        match args.get(1).map(|command| command.as_str()) {
+            Some("serve") | Some("crawl") | Some("db") | Some("export") | Some("import") |
+            Some("check") | Some("validate") => {}
+            _ => return Command::Serve(Settings::from_args(args, SERVE_DESCRIPTION)),
+        }
+
+        let command = args.remove(1);
+
+        args[0] = format!("{} {}", args[0], command);
+
+        match command.as_str() {
+            "serve" => Command::Serve(Settings::from_args(args, SERVE_DESCRIPTION)),
+            "crawl" => Command::Crawl(Settings::from_args(args, CRAWL_DESCRIPTION)),
+            "db" => Command::Database(DatabaseSettings::from_args(args)),
+            "export" => Command::Export(ExportSettings::from_args(args)),
+            "import" => Command::Import(ImportSettings::from_args(args)),
+            "check" => Command::Check(CheckSettings::from_args(args)),
+            _ => Command::Validate(ValidateSettings::from_args(args)),
+        }
+    }
 }
 
 
 impl Settings {
-    pub fn from_args() -> Settings {
+    pub fn from_args(args: Vec<String>, description: &str) -> Settings {
         info!("Parsing setting from command line arguments");
 
         let mut bind_address = None;
@@ -28,11 +150,15 @@ impl Settings {
         let mut create_database = false;
         let mut force = false;
         let mut disable_crawler = false;
+        let mut fixtures_path = None;
+        let mut record_path = None;
+        let mut replay_path = None;
+        let mut print_settings = false;
 
         {
             let mut ap = ArgumentParser::new();
 
-            ap.set_description("Online shop parsing tool.");
+            ap.set_description(description);
             ap.refer(&mut bind_address).add_option(
                 &["-b", "--bind"],
                 StoreOption,
@@ -46,7 +172,7 @@ impl Settings {
             ap.refer(&mut period).add_option(
                 &["-e", "--period"],
                 StoreOption,
-                "Update period in hours (default: 12)",
+                "Default update period in hours (default: 12)",
             );
             ap.refer(&mut config_path).add_option(
                 &["-c", "--config"],
@@ -73,35 +199,173 @@ impl Settings {
                 StoreTrue,
                 "Do not start background crawler thread (default: false)",
             );
-            ap.parse_args_or_exit();
+            ap.refer(&mut fixtures_path).add_option(
+                &["-x", "--fixtures"],
+                StoreOption,
+                "Load product pages from fixture directory instead of network",
+            );
+            ap.refer(&mut record_path).add_option(
+                &["--record"],
+                StoreOption,
+                "Record all HTTP responses to cassette directory",
+            );
+            ap.refer(&mut replay_path).add_option(
+                &["--replay"],
+                StoreOption,
+                "Replay HTTP responses from cassette directory instead of network",
+            );
+            ap.refer(&mut print_settings).add_option(
+                &["--print-settings"],
+                StoreTrue,
+                "Show effective settings with their sources and exit",
+            );
+
+            if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+                process::exit(code);
+            }
         }
 
-        let mut config = Self::default();
+        let mut settings = Self::default();
+
+        settings.config_path = match config_path {
+            Some(config_path) => {
+                settings.set_source("config", SettingSource::CommandLine);
+
+                config_path
+            }
+            None => {
+                match env_value("CONFIG") {
+                    Some(config_path) => {
+                        settings.set_source("config", SettingSource::environment("CONFIG"));
+
+                        config_path
+                    }
+                    None => DEFAULT_CONFIG_PATH.into(),
+                }
+            }
+        };
+        settings.apply_file();
+        settings.apply_environment();
 
         if let Some(bind_address) = bind_address {
-            config.bind_address = bind_address;
+            settings.bind_address = bind_address;
+            settings.set_source("bind", SettingSource::CommandLine);
         }
 
         if let Some(bind_port) = bind_port {
-            config.bind_port = bind_port;
+            settings.bind_port = bind_port;
+            settings.set_source("port", SettingSource::CommandLine);
         }
 
         if let Some(period) = period {
-            config.period = period;
+            settings.period = period;
+            settings.set_source("period", SettingSource::CommandLine);
         }
 
-        if let Some(config_path) = config_path {
-            config.config_path = config_path;
+        if let Some(database_path) = database_path {
+            settings.database_path = database_path;
+            settings.set_source("database", SettingSource::CommandLine);
         }
 
-        if let Some(database_path) = database_path {
-            config.database_path = database_path;
+        if disable_crawler {
+            settings.disable_crawler = true;
+            settings.set_source("disable_crawler", SettingSource::CommandLine);
         }
 
-        config.create_database |= create_database;
-        config.force |= force;
-        config.disable_crawler |= disable_crawler;
-        config
+        settings.create_database |= create_database;
+        settings.force |= force;
+        settings.fixtures_path = fixtures_path;
+        settings.record_path = record_path;
+        settings.replay_path = replay_path;
+        settings.print_settings = print_settings;
+        settings
+    }
+
+    fn apply_file(&mut self) {
+        let config = match file_settings(&self.config_path) {
+            Some(config) => config,
+            None => return,
+        };
+        let source = SettingSource::File { path: self.config_path.clone() };
+
+        if let Some(server) = config.server {
+            if let Some(bind_address) = server.bind {
+                self.bind_address = bind_address;
+                self.set_source("bind", source.clone());
+            }
+
+            if let Some(bind_port) = server.port {
+                self.bind_port = bind_port;
+                self.set_source("port", source.clone());
+            }
+
+            if let Some(database_path) = server.database {
+                self.database_path = database_path;
+                self.set_source("database", source.clone());
+            }
+        }
+
+        if let Some(crawler) = config.crawler {
+            if let Some(period) = crawler.period {
+                self.period = period;
+                self.set_source("period", source.clone());
+            }
+
+            if let Some(disabled) = crawler.disabled {
+                self.disable_crawler = disabled;
+                self.set_source("disable_crawler", source);
+            }
+        }
+    }
+
+    fn apply_environment(&mut self) {
+        if let Some(bind_address) = env_value("BIND") {
+            self.bind_address = bind_address;
+            self.set_source("bind", SettingSource::environment("BIND"));
+        }
+
+        if let Some(bind_port) = parse_env_value("PORT") {
+            self.bind_port = bind_port;
+            self.set_source("port", SettingSource::environment("PORT"));
+        }
+
+        if let Some(period) = parse_env_value("PERIOD") {
+            self.period = period;
+            self.set_source("period", SettingSource::environment("PERIOD"));
+        }
+
+        if let Some(database_path) = env_value("DATABASE") {
+            self.database_path = database_path;
+            self.set_source("database", SettingSource::environment("DATABASE"));
+        }
+
+        if let Some(disable_crawler) = parse_env_value("DISABLE_CRAWLER") {
+            self.disable_crawler = disable_crawler;
+            self.set_source("disable_crawler", SettingSource::environment("DISABLE_CRAWLER"));
+        }
+    }
+
+    fn set_source(&mut self, name: &'static str, source: SettingSource) {
+        self.sources.insert(name, source);
+    }
+
+    fn source(&self, name: &str) -> SettingSource {
+        self.sources.get(name).cloned().unwrap_or(SettingSource::Default)
+    }
+
+    pub fn print(&self) {
+        let values = [
+            ("config", self.config_path.clone()),
+            ("bind", self.bind_address.clone()),
+            ("port", self.bind_port.to_string()),
+            ("database", self.database_path.clone()),
+            ("period", self.period.to_string()),
+            ("disable_crawler", self.disable_crawler.to_string()),
+        ];
+
+        for &(name, ref value) in &values {
+            println!("{} = {} ({})", name, value, self.source(name));
+        }
     }
 
     pub fn bind_address(&self) -> String {
@@ -135,6 +399,22 @@ impl Settings {
     pub fn disable_crawler(&self) -> bool {
         self.disable_crawler
     }
+
+    pub fn fixtures_path(&self) -> Option<String> {
+        self.fixtures_path.clone()
+    }
+
+    pub fn record_path(&self) -> Option<String> {
+        self.record_path.clone()
+    }
+
+    pub fn replay_path(&self) -> Option<String> {
+        self.replay_path.clone()
+    }
+
+    pub fn print_settings(&self) -> bool {
+        self.print_settings
+    }
 }
 
 
@@ -144,11 +424,351 @@ impl Default for Settings {
             bind_address: "localhost".into(),
             bind_port: 8080,
             period: 12,
-            config_path: "config.toml".into(),
-            database_path: "local.sqlite".into(),
+            config_path: DEFAULT_CONFIG_PATH.into(),
+            database_path: DEFAULT_DATABASE_PATH.into(),
             create_database: false,
             force: false,
             disable_crawler: false,
+            fixtures_path: None,
+            record_path: None,
+            replay_path: None,
+            print_settings: false,
+            sources: BTreeMap::new(),
+        }
+    }
+}
+
+
+impl SettingSource {
+    fn environment(name: &str) -> SettingSource {
+        SettingSource::Environment { variable: format!("{}{}", ENV_PREFIX, name) }
+    }
+}
+
+
+impl Display for SettingSource {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            SettingSource::Default => write!(f, "default"),
+            SettingSource::File { ref path } => write!(f, "file {}", path),
+            SettingSource::Environment { ref variable } => write!(f, "environment {}", variable),
+            SettingSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+
+impl FromStr for DatabaseAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<DatabaseAction, String> {
+        match action {
+            "init" => Ok(DatabaseAction::Init),
+            "migrate" => Ok(DatabaseAction::Migrate),
+            "merge" => Ok(DatabaseAction::Merge),
+            _ => Err(format!("Unknown database action: {}", action)),
+        }
+    }
+}
+
+
+impl DatabaseSettings {
+    pub fn from_args(args: Vec<String>) -> DatabaseSettings {
+        info!("Parsing database setting from command line arguments");
+
+        let mut action = DatabaseAction::Migrate;
+        let mut database_path = None;
+        let mut config_path = None;
+        let mut force = false;
+
+        {
+            let mut ap = ArgumentParser::new();
+
+            ap.set_description("Create, update or clean up local database.");
+            ap.refer(&mut action)
+                .add_argument("action", Store, "Database action: init, migrate or merge")
+                .required();
+            ap.refer(&mut database_path).add_option(
+                &["-d", "--database"],
+                StoreOption,
+                "Path to local database (default: local.sqlite)",
+            );
+            ap.refer(&mut config_path).add_option(
+                &["-c", "--config"],
+                StoreOption,
+                "Path to configuration file (default: config.toml)",
+            );
+            ap.refer(&mut force).add_option(
+                &["-f", "--force"],
+                StoreTrue,
+                "Remove existing database before init (default: false)",
+            );
+
+            if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+                process::exit(code);
+            }
+        }
+
+        let config_path = config_path.unwrap_or_else(default_config_path);
+
+        DatabaseSettings {
+            action,
+            database_path: resolve_database_path(database_path, &config_path),
+            config_path,
+            force,
+        }
+    }
+
+    pub fn action(&self) -> DatabaseAction {
+        self.action
+    }
+
+    pub fn database_path(&self) -> String {
+        self.database_path.clone()
+    }
+
+    pub fn config_path(&self) -> String {
+        self.config_path.clone()
+    }
+
+    pub fn force(&self) -> bool {
+        self.force
+    }
+}
+
+
+impl ExportSettings {
+    pub fn from_args(args: Vec<String>) -> ExportSettings {
+        info!("Parsing export setting from command line arguments");
+
+        let mut database_path = None;
+        let mut config_path = None;
+        let mut output_path = None;
+
+        {
+            let mut ap = ArgumentParser::new();
+
+            ap.set_description("Export all product prices from local database to JSON.");
+            ap.refer(&mut database_path).add_option(
+                &["-d", "--database"],
+                StoreOption,
+                "Path to local database (default: local.sqlite)",
+            );
+            ap.refer(&mut config_path).add_option(
+                &["-c", "--config"],
+                StoreOption,
+                "Path to configuration file (default: config.toml)",
+            );
+            ap.refer(&mut output_path).add_option(
+                &["-o", "--output"],
+                StoreOption,
+                "Path to output file (default: standard output)",
+            );
+
+            if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+                process::exit(code);
+            }
+        }
+
+        let config_path = config_path.unwrap_or_else(default_config_path);
+
+        ExportSettings {
+            database_path: resolve_database_path(database_path, &config_path),
+            output_path,
+        }
+    }
+
+    pub fn database_path(&self) -> String {
+        self.database_path.clone()
+    }
+
+    pub fn output_path(&self) -> Option<String> {
+        self.output_path.clone()
+    }
+}
+
+
+impl ImportSettings {
+    pub fn from_args(args: Vec<String>) -> ImportSettings {
+        info!("Parsing import setting from command line arguments");
+
+        let mut database_path = None;
+        let mut config_path = None;
+        let mut input_path = String::new();
+
+        {
+            let mut ap = ArgumentParser::new();
+
+            ap.set_description("Import product prices from JSON file made by export command.");
+            ap.refer(&mut database_path).add_option(
+                &["-d", "--database"],
+                StoreOption,
+                "Path to local database (default: local.sqlite)",
+            );
+            ap.refer(&mut config_path).add_option(
+                &["-c", "--config"],
+                StoreOption,
+                "Path to configuration file (default: config.toml)",
+            );
+            ap.refer(&mut input_path)
+                .add_argument("file", Store, "Path to exported file")
+                .required();
+
+            if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+                process::exit(code);
+            }
+        }
+
+        let config_path = config_path.unwrap_or_else(default_config_path);
+
+        ImportSettings {
+            database_path: resolve_database_path(database_path, &config_path),
+            input_path,
+        }
+    }
+
+    pub fn database_path(&self) -> String {
+        self.database_path.clone()
+    }
+
+    pub fn input_path(&self) -> String {
+        self.input_path.clone()
+    }
+}
+
+
+impl CheckSettings {
+    pub fn from_args(args: Vec<String>) -> CheckSettings {
+        info!("Parsing check setting from command line arguments");
+
+        let mut config_path = None;
+        let mut shop_name = String::new();
+        let mut url = String::new();
+
+        {
+            let mut ap = ArgumentParser::new();
+
+            ap.set_description("Load product page once and show how shop selectors match it.");
+            ap.refer(&mut config_path).add_option(
+                &["-c", "--config"],
+                StoreOption,
+                "Path to configuration file (default: config.toml)",
+            );
+            ap.refer(&mut shop_name)
+                .add_option(&["-s", "--shop"], Store, "Shop name from configuration")
+                .required();
+            ap.refer(&mut url)
+                .add_argument("url", Store, "Product page URL")
+                .required();
+
+            if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+                process::exit(code);
+            }
+        }
+
+        CheckSettings {
+            config_path: config_path.unwrap_or_else(default_config_path),
+            shop_name,
+            url,
+        }
+    }
+
+    pub fn config_path(&self) -> String {
+        self.config_path.clone()
+    }
+
+    pub fn shop_name(&self) -> String {
+        self.shop_name.clone()
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+
+impl ValidateSettings {
+    pub fn from_args(args: Vec<String>) -> ValidateSettings {
+        info!("Parsing validate setting from command line arguments");
+
+        let mut config_path = None;
+
+        {
+            let mut ap = ArgumentParser::new();
+
+            ap.set_description("Validate configuration and show all found problems.");
+            ap.refer(&mut config_path).add_option(
+                &["-c", "--config"],
+                StoreOption,
+                "Path to configuration file (default: config.toml)",
+            );
+
+            if let Err(code) = ap.parse(args, &mut stdout(), &mut stderr()) {
+                process::exit(code);
+            }
+        }
+
+        ValidateSettings {
+            config_path: config_path.unwrap_or_else(default_config_path),
+        }
+    }
+
+    pub fn config_path(&self) -> String {
+        self.config_path.clone()
+    }
+}
+
+
+fn default_config_path() -> String {
+    env_value("CONFIG").unwrap_or_else(|| DEFAULT_CONFIG_PATH.into())
+}
+
+
+fn resolve_database_path(database_path: Option<String>, config_path: &str) -> String {
+    database_path
+        .or_else(|| env_value("DATABASE"))
+        .or_else(|| {
+            file_settings(config_path)
+                .and_then(|settings| settings.server)
+                .and_then(|server| server.database)
+        })
+        .unwrap_or_else(|| DEFAULT_DATABASE_PATH.into())
+}
+
+
+fn file_settings(config_path: &str) -> Option<SettingsConfig> {
+    if !Path::new(config_path).exists() {
+        return None;
+    }
+
+    match read_settings(config_path) {
+        Ok(settings) => Some(settings),
+        Err(error) => {
+            error!("Can not read settings from configuration file: {}", error);
+
+            process::exit(1);
+        }
+    }
+}
+
+
+fn env_value(name: &str) -> Option<String> {
+    env::var(format!("{}{}", ENV_PREFIX, name)).ok()
+}
+
+
+fn parse_env_value<T>(name: &str) -> Option<T>
+where
+    T: FromStr,
+{
+    let value = env_value(name)?;
+
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            error!("Invalid value `{}` of environment variable {}{}", value, ENV_PREFIX, name);
+
+            process::exit(1);
         }
     }
 }
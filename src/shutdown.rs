@@ -0,0 +1,93 @@
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use ctrlc;
+
+
+const DRAIN_TIMEOUT: u64 = 30;
+const DRAIN_INTERVAL: u64 = 100;
+
+
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    active: Arc<AtomicUsize>,
+}
+
+
+#[derive(Debug)]
+pub struct WorkGuard {
+    active: Arc<AtomicUsize>,
+}
+
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        Shutdown {
+            requested: Arc::new(AtomicBool::new(false)),
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn install(&self) -> Result<(), ctrlc::Error> {
+        let shutdown = self.clone();
+
+        ctrlc::set_handler(move || shutdown.request())
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    pub fn work(&self) -> WorkGuard {
+        self.active.fetch_add(1, Ordering::SeqCst);
+
+        WorkGuard { active: self.active.clone() }
+    }
+
+    pub fn wait(&self) {
+        while !self.is_requested() {
+            thread::sleep(Duration::from_millis(DRAIN_INTERVAL));
+        }
+
+        while self.active.load(Ordering::SeqCst) > 0 {
+            thread::sleep(Duration::from_millis(DRAIN_INTERVAL));
+        }
+    }
+
+    fn request(&self) {
+        if self.requested.swap(true, Ordering::SeqCst) {
+            warn!("Second termination signal received, exiting immediately");
+
+            process::exit(1);
+        }
+
+        info!("Termination signal received, waiting for active work to finish");
+
+        let shutdown = self.clone();
+
+        thread::spawn(move || shutdown.expire());
+    }
+
+    fn expire(&self) {
+        thread::sleep(Duration::from_secs(DRAIN_TIMEOUT));
+
+        let active = self.active.load(Ordering::SeqCst);
+
+        warn!("Shutdown timeout expired, {} active tasks interrupted", active);
+
+        process::exit(1);
+    }
+}
+
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,130 @@
+use super::CanonicalConfig;
+use super::TrailingSlash;
+
+
+const HTTP_SCHEMA: &'static str = "http://";
+const HTTPS_SCHEMA: &'static str = "https://";
+const DEFAULT_STRIP_PARAMS: &'static [&'static str] = &["utm_*", "gclid", "fbclid", "yclid"];
+
+
+pub fn canonicalize(url: &str, rules: &CanonicalConfig) -> String {
+    let url = url.trim();
+    let (url, fragment) = match url.find('#') {
+        Some(position) => (&url[..position], Some(&url[position..])),
+        None => (url, None),
+    };
+    let (url, query) = match url.find('?') {
+        Some(position) => (&url[..position], Some(&url[position + 1..])),
+        None => (url, None),
+    };
+    let lowercase_url = url.to_lowercase();
+    let (schema, rest) = if lowercase_url.starts_with(HTTPS_SCHEMA) {
+        (HTTPS_SCHEMA, &url[HTTPS_SCHEMA.len()..])
+    } else if lowercase_url.starts_with(HTTP_SCHEMA) {
+        (HTTP_SCHEMA, &url[HTTP_SCHEMA.len()..])
+    } else {
+        return url.to_string() + &query.map(|query| format!("?{}", query)).unwrap_or_default() +
+            fragment.unwrap_or("");
+    };
+    let schema = if rules.force_https.unwrap_or(false) {
+        HTTPS_SCHEMA
+    } else {
+        schema
+    };
+    let (host, path) = match rest.find('/') {
+        Some(position) => (&rest[..position], &rest[position..]),
+        None => (rest, "/"),
+    };
+    let mut host = host.to_lowercase();
+
+    if (schema == HTTP_SCHEMA && host.ends_with(":80")) ||
+        (schema == HTTPS_SCHEMA && host.ends_with(":443"))
+    {
+        let position = host.rfind(':').unwrap_or_else(|| host.len());
+
+        host.truncate(position);
+    }
+
+    let mut result = format!("{}{}{}", schema, host, canonical_path(path, rules));
+
+    if let Some(query) = query {
+        let query = canonical_query(query, rules);
+
+        if !query.is_empty() {
+            result.push('?');
+            result.push_str(&query);
+        }
+    }
+
+    if let Some(fragment) = fragment {
+        if !rules.drop_fragment.unwrap_or(true) {
+            result.push_str(fragment);
+        }
+    }
+
+    result
+}
+
+
+fn canonical_path(path: &str, rules: &CanonicalConfig) -> String {
+    match rules.trailing_slash.unwrap_or(TrailingSlash::Keep) {
+        TrailingSlash::Keep => path.to_string(),
+        TrailingSlash::Strip if path.len() > 1 => path.trim_right_matches('/').to_string(),
+        TrailingSlash::Strip => path.to_string(),
+        TrailingSlash::Add if path.ends_with('/') => path.to_string(),
+        TrailingSlash::Add => format!("{}/", path),
+    }
+}
+
+
+fn canonical_query(query: &str, rules: &CanonicalConfig) -> String {
+    let mut params: Vec<_> = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or("");
+            let kept = match rules.keep_params {
+                Some(ref patterns) => patterns.iter().any(|pattern| wildcard_match(pattern, name)),
+                None => true,
+            };
+            let stripped = match rules.strip_params {
+                Some(ref patterns) => patterns.iter().any(|pattern| wildcard_match(pattern, name)),
+                None => DEFAULT_STRIP_PARAMS.iter().any(|pattern| wildcard_match(pattern, name)),
+            };
+
+            kept && !stripped
+        })
+        .collect();
+
+    if rules.sort_params.unwrap_or(false) {
+        params.sort();
+    }
+
+    params.join("&")
+}
+
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+
+    if !text.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..];
+    let parts: Vec<_> = parts.collect();
+
+    for (index, part) in parts.iter().enumerate() {
+        if index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.is_empty()
+}
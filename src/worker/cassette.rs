@@ -0,0 +1,104 @@
+use std::fs::create_dir_all;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json;
+
+use super::ProductError;
+
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    #[serde(skip)]
+    pub body: Vec<u8>,
+}
+
+
+impl Exchange {
+    pub fn read(directory: &Path, url: &str) -> Result<Exchange, ProductError> {
+        let (meta_path, body_path) = cassette_paths(directory, url);
+        let mut meta = String::new();
+        let mut body = Vec::new();
+
+        File::open(&meta_path)
+            .and_then(|mut file| file.read_to_string(&mut meta))
+            .map_err(|error| {
+                ProductError::cassette_error(
+                    format!("can not read `{}`: {}", meta_path.display(), error),
+                )
+            })?;
+        File::open(&body_path)
+            .and_then(|mut file| file.read_to_end(&mut body))
+            .map_err(|error| {
+                ProductError::cassette_error(
+                    format!("can not read `{}`: {}", body_path.display(), error),
+                )
+            })?;
+
+        let mut exchange: Exchange = serde_json::from_str(&meta).map_err(|error| {
+            ProductError::cassette_error(format!("`{}`: {}", meta_path.display(), error))
+        })?;
+
+        if exchange.url != url {
+            return Err(ProductError::cassette_error(format!(
+                "`{}` contains `{}` instead of `{}`",
+                meta_path.display(),
+                exchange.url,
+                url
+            )));
+        }
+
+        exchange.body = body;
+
+        Ok(exchange)
+    }
+
+    pub fn write(&self, directory: &Path) -> Result<(), ProductError> {
+        let (meta_path, body_path) = cassette_paths(directory, &self.url);
+        let meta = serde_json::to_string_pretty(self).map_err(|error| {
+            ProductError::cassette_error(format!("`{}`: {}", meta_path.display(), error))
+        })?;
+
+        create_dir_all(directory)?;
+        File::create(&meta_path)?.write_all(meta.as_bytes())?;
+        File::create(&body_path)?.write_all(&self.body)?;
+
+        Ok(())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|&&(ref key, _)| key.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.as_str())
+    }
+
+    pub fn is_redirection(&self) -> bool {
+        self.status >= 300 && self.status < 400
+    }
+}
+
+
+fn cassette_paths(directory: &Path, url: &str) -> (PathBuf, PathBuf) {
+    let mut hash = FNV_OFFSET_BASIS;
+
+    for byte in url.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    (
+        directory.join(format!("{:016x}.json", hash)),
+        directory.join(format!("{:016x}.body", hash)),
+    )
+}
@@ -0,0 +1,157 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Error as FmtError;
+use std::fmt::Formatter;
+use std::path::Path;
+
+use kuchiki::parse_html;
+use kuchiki::NodeRef;
+use tendril::TendrilSink;
+
+use super::extract_product;
+use super::LoaderMode;
+use super::PriceLoader;
+use super::PriceLoaderError;
+use super::ProductError;
+use super::read_config;
+use super::ReadConfigError;
+use super::script::ScriptCache;
+use super::Selector;
+
+
+#[derive(Debug, Clone)]
+pub enum CheckError {
+    ConfigError { description: String },
+    NoSuchShop { shop_name: String },
+    LoaderError { description: String },
+    ProductError { description: String },
+}
+
+
+impl CheckError {
+    fn no_such_shop<S>(shop_name: S) -> CheckError
+    where
+        S: Into<String>,
+    {
+        CheckError::NoSuchShop { shop_name: shop_name.into() }
+    }
+}
+
+
+impl From<ReadConfigError> for CheckError {
+    fn from(error: ReadConfigError) -> CheckError {
+        CheckError::ConfigError { description: format!("{}", error) }
+    }
+}
+
+
+impl From<PriceLoaderError> for CheckError {
+    fn from(error: PriceLoaderError) -> CheckError {
+        CheckError::LoaderError { description: format!("{}", error) }
+    }
+}
+
+
+impl From<ProductError> for CheckError {
+    fn from(error: ProductError) -> CheckError {
+        CheckError::ProductError { description: format!("{}", error) }
+    }
+}
+
+
+impl Display for CheckError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            CheckError::ConfigError { ref description } => {
+                write!(f, "Configuration error: {}", description)
+            }
+            CheckError::NoSuchShop { ref shop_name } => {
+                write!(f, "Shop `{}` not found in configuration", shop_name)
+            }
+            CheckError::LoaderError { ref description } => {
+                write!(f, "Loader error: {}", description)
+            }
+            CheckError::ProductError { ref description } => {
+                write!(f, "Product error: {}", description)
+            }
+        }
+    }
+}
+
+
+impl Error for CheckError {
+    fn description(&self) -> &str {
+        match *self {
+            CheckError::ConfigError { .. } => "Configuration error",
+            CheckError::NoSuchShop { .. } => "Shop not found in configuration",
+            CheckError::LoaderError { .. } => "Loader error",
+            CheckError::ProductError { .. } => "Product error",
+        }
+    }
+}
+
+
+pub fn check_product<P>(config_path: P, shop_name: &str, url: &str) -> Result<(), CheckError>
+where
+    P: AsRef<Path>,
+{
+    let config = read_config(config_path)?;
+    let shop = config
+        .shops
+        .iter()
+        .find(|shop| shop.name == shop_name)
+        .ok_or_else(|| CheckError::no_such_shop(shop_name))?;
+    let canonical_url = config.canonical_url(shop_name, url);
+    let shop = match config.products.iter().find(|product| {
+        product.shop_name == shop_name && product.url == canonical_url
+    }) {
+        Some(product) => shop.with_overrides(product),
+        None => shop.clone(),
+    };
+    let mut loader = PriceLoader::new(LoaderMode::Network)?;
+    let page = loader.fetch(url, &shop.cookies)?;
+
+    println!("Status: {}", page.status);
+    println!("URL: {}", page.url);
+
+    let document = parse_html().from_utf8().one(page.content.as_slice());
+
+    if let Some(ref selector) = shop.name_selector {
+        print_matches(&document, "Name", selector);
+    }
+
+    if let Some(ref selector) = shop.price_selector {
+        print_matches(&document, "Price", selector);
+    }
+
+    let product = extract_product(url, &page.content, &shop, &mut ScriptCache::new())?;
+
+    println!("Name: {}", product.name);
+
+    if let Some(ref price_text) = product.price_text {
+        println!("Price text: {}", price_text);
+    }
+
+    println!("Price: {}", product.price);
+
+    Ok(())
+}
+
+
+fn print_matches(document: &NodeRef, kind: &str, selector: &Selector) {
+    for (alternative, selector) in selector.alternatives().into_iter().enumerate() {
+        println!("{} selector #{} `{}`:", kind, alternative, selector);
+
+        match document.select(selector) {
+            Ok(css_matches) => {
+                for (index, css_match) in css_matches.enumerate() {
+                    let text = css_match.text_contents();
+                    let text: Vec<_> = text.split_whitespace().collect();
+
+                    println!("    [{}] {}", index, text.join(" "));
+                }
+            }
+            Err(()) => println!("    invalid selector"),
+        }
+    }
+}
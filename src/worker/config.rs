@@ -1,26 +1,151 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::default::Default;
+use std::env;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::path::Path;
+use std::path::PathBuf;
+
+use hyper::Uri;
+use kuchiki::Selectors;
+use regex::Regex;
+
+use super::canonical::canonicalize;
+use super::loader::FILE_SCHEMA;
+use super::outline::Outline;
+use super::schedule::Schedule;
 
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    pub include: Option<Vec<String>>,
+    pub secrets: Option<String>,
+    #[serde(default)]
     pub shops: Vec<ShopConfig>,
+    #[serde(default)]
+    pub templates: Vec<ShopConfig>,
+    #[serde(default)]
     pub products: Vec<ProductConfig>,
+    pub categories: Option<Vec<CategoryConfig>>,
+    pub canonical: Option<CanonicalConfig>,
+    pub server: Option<ServerConfig>,
+    pub crawler: Option<CrawlerConfig>,
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+    #[serde(skip)]
+    pub include_directories: Vec<PathBuf>,
+    #[serde(skip)]
+    pub secret_values: HashMap<String, String>,
+    #[serde(skip)]
+    pub unknown_keys: Vec<(String, ConfigSource)>,
+    #[serde(skip)]
+    pub ignored_settings: Vec<(String, ConfigSource)>,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSource {
+    pub file: PathBuf,
+    pub line: Option<usize>,
 }
 
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ShopConfig {
     pub name: String,
-    pub name_selector: String,
-    pub price_selector: String,
+    pub extends: Option<String>,
+    pub name_selector: Option<Selector>,
+    pub price_selector: Option<Selector>,
     pub price_factor: Option<f64>,
     pub price_index: Option<usize>,
+    pub price_pick: Option<PricePick>,
     pub cookies: Option<HashMap<String, String>>,
+    pub sitemaps: Option<Vec<SitemapConfig>>,
+    pub script: Option<String>,
+    pub script_timeout: Option<u64>,
+    pub schedule: Option<String>,
+    pub canonical: Option<CanonicalConfig>,
+    #[serde(skip)]
+    pub source: Option<ConfigSource>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServerConfig {
+    pub bind: Option<String>,
+    pub port: Option<u16>,
+    pub database: Option<String>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CrawlerConfig {
+    pub period: Option<usize>,
+    pub disabled: Option<bool>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SettingsConfig {
+    pub server: Option<ServerConfig>,
+    pub crawler: Option<CrawlerConfig>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CanonicalConfig {
+    pub strip_params: Option<Vec<String>>,
+    pub keep_params: Option<Vec<String>>,
+    pub sort_params: Option<bool>,
+    pub force_https: Option<bool>,
+    pub drop_fragment: Option<bool>,
+    pub trailing_slash: Option<TrailingSlash>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingSlash {
+    Keep,
+    Strip,
+    Add,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Selector {
+    Single(String),
+    Chain(Vec<String>),
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(untagged)]
+pub enum PricePick {
+    Index(isize),
+    Named(PricePickName),
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PricePickName {
+    First,
+    Last,
+    Min,
+    Max,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SitemapConfig {
+    pub url: String,
+    pub include: String,
+    pub category: String,
+    pub limit: Option<usize>,
 }
 
 
@@ -29,27 +154,316 @@ pub struct ProductConfig {
     pub shop_name: String,
     pub category: String,
     pub url: String,
+    pub group: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub max_price_change: Option<f64>,
+    pub name_selector: Option<Selector>,
+    pub price_selector: Option<Selector>,
+    pub price_factor: Option<f64>,
+    pub price_index: Option<usize>,
+    pub price_pick: Option<PricePick>,
+    pub cookies: Option<HashMap<String, String>>,
+    pub script: Option<String>,
+    pub script_timeout: Option<u64>,
+    pub schedule: Option<String>,
+    #[serde(skip)]
+    pub source: Option<ConfigSource>,
+}
+
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryConfig {
+    pub name: String,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub max_price_change: Option<f64>,
+    #[serde(skip)]
+    pub source: Option<ConfigSource>,
 }
 
 
 #[derive(Debug, Clone)]
 pub enum ConfigError {
-    DuplicateShopName { shop_name: String },
-    NoSuchShopExists { shop_name: String },
+    DuplicateShopName {
+        shop_name: String,
+        source: Option<ConfigSource>,
+        first_source: Option<ConfigSource>,
+    },
+    NoSuchShopExists {
+        shop_name: String,
+        source: Option<ConfigSource>,
+    },
+    InvalidSitemapPattern {
+        shop_name: String,
+        pattern: String,
+        source: Option<ConfigSource>,
+    },
+    InvalidPriceLimits {
+        name: String,
+        source: Option<ConfigSource>,
+    },
+    InvalidSchedule {
+        name: String,
+        schedule: String,
+        description: String,
+        source: Option<ConfigSource>,
+    },
+    UnresolvedVariable {
+        name: String,
+        variable: String,
+        source: Option<ConfigSource>,
+    },
+    DuplicateProductUrl {
+        url: String,
+        source: Option<ConfigSource>,
+        first_source: Option<ConfigSource>,
+    },
+    InvalidUrl {
+        url: String,
+        source: Option<ConfigSource>,
+    },
+    VariableInUrl {
+        url: String,
+        source: Option<ConfigSource>,
+    },
+    InvalidSelector {
+        name: String,
+        selector: String,
+        source: Option<ConfigSource>,
+    },
+    NegativePriceFactor {
+        name: String,
+        source: Option<ConfigSource>,
+    },
+    UnknownKey {
+        key: String,
+        source: Option<ConfigSource>,
+    },
+    IgnoredSettings {
+        section: String,
+        source: Option<ConfigSource>,
+    },
+    MissingParent {
+        shop_name: String,
+        parent: String,
+        source: Option<ConfigSource>,
+    },
+    InheritanceCycle {
+        shop_name: String,
+        source: Option<ConfigSource>,
+    },
+    MissingSelector {
+        shop_name: String,
+        selector: String,
+        source: Option<ConfigSource>,
+    },
+}
+
+
+impl ConfigSource {
+    pub fn new(file: &Path, line: Option<usize>) -> ConfigSource {
+        ConfigSource {
+            file: file.to_path_buf(),
+            line,
+        }
+    }
+}
+
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.file.display(), line),
+            None => write!(f, "{}", self.file.display()),
+        }
+    }
 }
 
 
 impl Display for ConfigError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match *self {
-            ConfigError::DuplicateShopName { ref shop_name } => {
-                write!(f, "Duplicate shop name: {}", shop_name)
+            ConfigError::DuplicateShopName {
+                ref shop_name,
+                ref source,
+                ref first_source,
+            } => {
+                write!(
+                    f,
+                    "Duplicate shop name `{}` in {} (first defined in {})",
+                    shop_name,
+                    source_name(source),
+                    source_name(first_source)
+                )
+            }
+            ConfigError::NoSuchShopExists {
+                ref shop_name,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Shop `{}` exists in product from {}, but not found in shops",
+                    shop_name,
+                    source_name(source)
+                )
+            }
+            ConfigError::InvalidSitemapPattern {
+                ref shop_name,
+                ref pattern,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Shop `{}` from {} has invalid sitemap include pattern: {}",
+                    shop_name,
+                    source_name(source),
+                    pattern
+                )
+            }
+            ConfigError::InvalidPriceLimits {
+                ref name,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Price limits for `{}` from {} are invalid: minimal price greater than \
+                     maximal or price change is negative",
+                    name,
+                    source_name(source)
+                )
+            }
+            ConfigError::InvalidSchedule {
+                ref name,
+                ref schedule,
+                ref description,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Schedule `{}` for `{}` from {} is invalid: {}",
+                    schedule,
+                    name,
+                    source_name(source),
+                    description
+                )
+            }
+            ConfigError::UnresolvedVariable {
+                ref name,
+                ref variable,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Variable `{}` used by `{}` from {} is not found in secrets and environment",
+                    variable,
+                    name,
+                    source_name(source)
+                )
+            }
+            ConfigError::DuplicateProductUrl {
+                ref url,
+                ref source,
+                ref first_source,
+            } => {
+                write!(
+                    f,
+                    "Duplicate product URL `{}` in {} (first defined in {})",
+                    url,
+                    source_name(source),
+                    source_name(first_source)
+                )
+            }
+            ConfigError::InvalidUrl {
+                ref url,
+                ref source,
+            } => write!(f, "Invalid URL `{}` in {}", url, source_name(source)),
+            ConfigError::VariableInUrl {
+                ref url,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Product URL `{}` from {} can not contain variables",
+                    url,
+                    source_name(source)
+                )
+            }
+            ConfigError::InvalidSelector {
+                ref name,
+                ref selector,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Selector `{}` for `{}` from {} is not valid CSS selector",
+                    selector,
+                    name,
+                    source_name(source)
+                )
+            }
+            ConfigError::NegativePriceFactor {
+                ref name,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Price factor for `{}` from {} is negative",
+                    name,
+                    source_name(source)
+                )
+            }
+            ConfigError::UnknownKey {
+                ref key,
+                ref source,
+            } => write!(f, "Unknown key `{}` in {}", key, source_name(source)),
+            ConfigError::IgnoredSettings {
+                ref section,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Section `{}` in {} is ignored, settings are read from the main file only",
+                    section,
+                    source_name(source)
+                )
             }
-            ConfigError::NoSuchShopExists { ref shop_name } => {
+            ConfigError::MissingParent {
+                ref shop_name,
+                ref parent,
+                ref source,
+            } => {
                 write!(
                     f,
-                    "Shop `{}` exists in product, but not found in shops",
-                    shop_name
+                    "Shop `{}` from {} extends `{}`, but it is not found in shops and templates",
+                    shop_name,
+                    source_name(source),
+                    parent
+                )
+            }
+            ConfigError::InheritanceCycle {
+                ref shop_name,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Shop `{}` from {} extends itself through parents",
+                    shop_name,
+                    source_name(source)
+                )
+            }
+            ConfigError::MissingSelector {
+                ref shop_name,
+                ref selector,
+                ref source,
+            } => {
+                write!(
+                    f,
+                    "Shop `{}` from {} has no `{}` and does not inherit it",
+                    shop_name,
+                    source_name(source),
+                    selector
                 )
             }
         }
@@ -60,52 +474,903 @@ impl Display for ConfigError {
 impl Default for Config {
     fn default() -> Config {
         Config {
+            include: None,
+            secrets: None,
             shops: Vec::new(),
+            templates: Vec::new(),
             products: Vec::new(),
+            categories: None,
+            canonical: None,
+            server: None,
+            crawler: None,
+            sources: Vec::new(),
+            include_directories: Vec::new(),
+            secret_values: HashMap::new(),
+            unknown_keys: Vec::new(),
+            ignored_settings: Vec::new(),
         }
     }
 }
 
 
 impl ConfigError {
-    fn duplicate_shop_name<S>(shop_name: S) -> ConfigError
+    fn duplicate_shop_name<S>(
+        shop_name: S,
+        source: Option<ConfigSource>,
+        first_source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::DuplicateShopName {
+            shop_name: shop_name.into(),
+            source,
+            first_source,
+        }
+    }
+
+    fn no_such_shop_exists<S>(shop_name: S, source: Option<ConfigSource>) -> ConfigError
     where
         S: Into<String>,
     {
-        ConfigError::DuplicateShopName { shop_name: shop_name.into() }
+        ConfigError::NoSuchShopExists {
+            shop_name: shop_name.into(),
+            source,
+        }
+    }
+
+    fn invalid_sitemap_pattern<S1, S2>(
+        shop_name: S1,
+        pattern: S2,
+        source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ConfigError::InvalidSitemapPattern {
+            shop_name: shop_name.into(),
+            pattern: pattern.into(),
+            source,
+        }
     }
 
-    fn no_such_shop_exists<S>(shop_name: S) -> ConfigError
+    fn invalid_price_limits<S>(name: S, source: Option<ConfigSource>) -> ConfigError
     where
         S: Into<String>,
     {
-        ConfigError::NoSuchShopExists { shop_name: shop_name.into() }
+        ConfigError::InvalidPriceLimits {
+            name: name.into(),
+            source,
+        }
+    }
+
+    fn invalid_schedule<S1, S2, S3>(
+        name: S1,
+        schedule: S2,
+        description: S3,
+        source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        ConfigError::InvalidSchedule {
+            name: name.into(),
+            schedule: schedule.into(),
+            description: description.into(),
+            source,
+        }
+    }
+
+    fn unresolved_variable<S1, S2>(
+        name: S1,
+        variable: S2,
+        source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ConfigError::UnresolvedVariable {
+            name: name.into(),
+            variable: variable.into(),
+            source,
+        }
+    }
+
+    fn duplicate_product_url<S>(
+        url: S,
+        source: Option<ConfigSource>,
+        first_source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::DuplicateProductUrl {
+            url: url.into(),
+            source,
+            first_source,
+        }
+    }
+
+    fn invalid_url<S>(url: S, source: Option<ConfigSource>) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::InvalidUrl {
+            url: url.into(),
+            source,
+        }
+    }
+
+    fn variable_in_url<S>(url: S, source: Option<ConfigSource>) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::VariableInUrl {
+            url: url.into(),
+            source,
+        }
+    }
+
+    fn invalid_selector<S1, S2>(name: S1, selector: S2, source: Option<ConfigSource>) -> ConfigError
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ConfigError::InvalidSelector {
+            name: name.into(),
+            selector: selector.into(),
+            source,
+        }
+    }
+
+    fn negative_price_factor<S>(name: S, source: Option<ConfigSource>) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::NegativePriceFactor {
+            name: name.into(),
+            source,
+        }
+    }
+
+    fn unknown_key<S>(key: S, source: Option<ConfigSource>) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::UnknownKey {
+            key: key.into(),
+            source,
+        }
+    }
+
+    fn ignored_settings<S>(section: S, source: Option<ConfigSource>) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::IgnoredSettings {
+            section: section.into(),
+            source,
+        }
+    }
+
+    fn missing_parent<S1, S2>(
+        shop_name: S1,
+        parent: S2,
+        source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ConfigError::MissingParent {
+            shop_name: shop_name.into(),
+            parent: parent.into(),
+            source,
+        }
+    }
+
+    fn inheritance_cycle<S>(shop_name: S, source: Option<ConfigSource>) -> ConfigError
+    where
+        S: Into<String>,
+    {
+        ConfigError::InheritanceCycle {
+            shop_name: shop_name.into(),
+            source,
+        }
+    }
+
+    fn missing_selector<S1, S2>(
+        shop_name: S1,
+        selector: S2,
+        source: Option<ConfigSource>,
+    ) -> ConfigError
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+    {
+        ConfigError::MissingSelector {
+            shop_name: shop_name.into(),
+            selector: selector.into(),
+            source,
+        }
+    }
+
+    pub fn is_warning(&self) -> bool {
+        match *self {
+            ConfigError::UnknownKey { .. } |
+            ConfigError::IgnoredSettings { .. } |
+            ConfigError::DuplicateProductUrl { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+
+impl Selector {
+    pub fn alternatives(&self) -> Vec<&str> {
+        match *self {
+            Selector::Single(ref selector) => vec![selector.as_str()],
+            Selector::Chain(ref selectors) => {
+                selectors.iter().map(|selector| selector.as_str()).collect()
+            }
+        }
+    }
+}
+
+
+impl ShopConfig {
+    fn inherit(&mut self, parent: &ShopConfig) {
+        if self.name_selector.is_none() {
+            self.name_selector = parent.name_selector.clone();
+        }
+
+        if self.price_selector.is_none() {
+            self.price_selector = parent.price_selector.clone();
+        }
+
+        if self.price_factor.is_none() {
+            self.price_factor = parent.price_factor;
+        }
+
+        if self.price_index.is_none() && self.price_pick.is_none() {
+            self.price_index = parent.price_index;
+            self.price_pick = parent.price_pick;
+        }
+
+        if let Some(ref cookies) = parent.cookies {
+            let mut result = cookies.clone();
+
+            result.extend(self.cookies.take().unwrap_or_default());
+            self.cookies = Some(result);
+        }
+
+        if self.script.is_none() {
+            self.script = parent.script.clone();
+        }
+
+        if self.script_timeout.is_none() {
+            self.script_timeout = parent.script_timeout;
+        }
+
+        if self.schedule.is_none() {
+            self.schedule = parent.schedule.clone();
+        }
+
+        if let Some(ref canonical) = parent.canonical {
+            let result = match self.canonical {
+                Some(ref own) => canonical.with_overrides(own),
+                None => canonical.clone(),
+            };
+
+            self.canonical = Some(result);
+        }
+    }
+
+    pub fn with_overrides(&self, product: &ProductConfig) -> ShopConfig {
+        let mut result = self.clone();
+
+        if product.name_selector.is_some() {
+            result.name_selector = product.name_selector.clone();
+        }
+
+        if product.price_selector.is_some() {
+            result.price_selector = product.price_selector.clone();
+        }
+
+        if product.price_factor.is_some() {
+            result.price_factor = product.price_factor;
+        }
+
+        if product.price_index.is_some() || product.price_pick.is_some() {
+            result.price_index = product.price_index;
+            result.price_pick = product.price_pick;
+        }
+
+        if let Some(ref cookies) = product.cookies {
+            result
+                .cookies
+                .get_or_insert_with(HashMap::new)
+                .extend(cookies.clone());
+        }
+
+        if product.script.is_some() {
+            result.script = product.script.clone();
+        }
+
+        if product.script_timeout.is_some() {
+            result.script_timeout = product.script_timeout;
+        }
+
+        result
+    }
+}
+
+
+impl CanonicalConfig {
+    pub fn with_overrides(&self, other: &CanonicalConfig) -> CanonicalConfig {
+        CanonicalConfig {
+            strip_params: other.strip_params.clone().or_else(|| self.strip_params.clone()),
+            keep_params: other.keep_params.clone().or_else(|| self.keep_params.clone()),
+            sort_params: other.sort_params.or(self.sort_params),
+            force_https: other.force_https.or(self.force_https),
+            drop_fragment: other.drop_fragment.or(self.drop_fragment),
+            trailing_slash: other.trailing_slash.or(self.trailing_slash),
+        }
+    }
+}
+
+
+impl ProductConfig {
+    pub fn new<S1, S2, S3>(shop_name: S1, category: S2, url: S3) -> ProductConfig
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+    {
+        ProductConfig {
+            shop_name: shop_name.into(),
+            category: category.into(),
+            url: url.into(),
+            group: None,
+            tags: None,
+            min_price: None,
+            max_price: None,
+            max_price_change: None,
+            name_selector: None,
+            price_selector: None,
+            price_factor: None,
+            price_index: None,
+            price_pick: None,
+            cookies: None,
+            script: None,
+            script_timeout: None,
+            schedule: None,
+            source: None,
+        }
     }
 }
 
 
 impl Config {
-    pub fn validate(&self) -> Option<ConfigError> {
-        let mut shop_names = HashSet::new();
+    pub fn merge(&mut self, other: Config) {
+        self.shops.extend(other.shops);
+        self.templates.extend(other.templates);
+        self.products.extend(other.products);
+
+        if let Some(categories) = other.categories {
+            self.categories
+                .get_or_insert_with(Vec::new)
+                .extend(categories);
+        }
+
+        if let Some(canonical) = other.canonical {
+            let result = match self.canonical {
+                Some(ref own) => own.with_overrides(&canonical),
+                None => canonical,
+            };
+
+            self.canonical = Some(result);
+        }
+
+        self.sources.extend(other.sources);
+        self.include_directories.extend(other.include_directories);
+        self.secret_values.extend(other.secret_values);
+        self.unknown_keys.extend(other.unknown_keys);
+        self.ignored_settings.extend(other.ignored_settings);
+    }
+
+    pub fn set_source(&mut self, source: &Path, outline: &Outline) {
+        for (index, shop) in self.shops.iter_mut().enumerate() {
+            let line = outline.item_line("shops", index);
+
+            shop.source = Some(ConfigSource::new(source, line));
+        }
+
+        for (index, template) in self.templates.iter_mut().enumerate() {
+            let line = outline.item_line("templates", index);
+
+            template.source = Some(ConfigSource::new(source, line));
+        }
+
+        for (index, product) in self.products.iter_mut().enumerate() {
+            let line = outline.item_line("products", index);
+
+            product.source = Some(ConfigSource::new(source, line));
+        }
+
+        let categories = self.categories.iter_mut().flat_map(|categories| categories);
+
+        for (index, category) in categories.enumerate() {
+            let line = outline.item_line("categories", index);
+
+            category.source = Some(ConfigSource::new(source, line));
+        }
+
+        self.sources.push(source.to_path_buf());
+    }
+
+    pub fn resolve_scripts(&mut self, directory: &Path) {
+        let shop_scripts = self.shops
+            .iter_mut()
+            .chain(&mut self.templates)
+            .filter_map(|shop| shop.script.as_mut());
+        let product_scripts = self.products
+            .iter_mut()
+            .filter_map(|product| product.script.as_mut());
+
+        for script in shop_scripts.chain(product_scripts) {
+            *script = directory.join(&*script).to_string_lossy().into_owned();
+        }
+    }
+
+    pub fn remove_duplicate_products(&mut self) {
+        let mut urls = HashSet::new();
+
+        self.products.retain(|product| urls.insert(product.url.clone()));
+    }
+
+    pub fn category(&self, name: &str) -> Option<&CategoryConfig> {
+        let categories = match self.categories {
+            Some(ref categories) => categories,
+            None => return None,
+        };
+        let mut path = category_path(name);
+
+        while !path.is_empty() {
+            let category = categories
+                .iter()
+                .find(|category| category_path(&category.name) == path);
+
+            if category.is_some() {
+                return category;
+            }
+
+            path.pop();
+        }
+
+        None
+    }
+
+    pub fn resolve_inheritance(&mut self) {
+        let resolved: Vec<_> = self.shops
+            .iter()
+            .map(|shop| {
+                let mut result = shop.clone();
+                let mut visited = HashSet::new();
+                let mut parent_name = shop.extends.clone();
+
+                while let Some(name) = parent_name {
+                    if !visited.insert(name.clone()) {
+                        break;
+                    }
+
+                    let parent = match self.parent_shop(&name) {
+                        Some(parent) => parent,
+                        None => break,
+                    };
+
+                    result.inherit(parent);
+                    parent_name = parent.extends.clone();
+                }
+
+                result
+            })
+            .collect();
+
+        self.shops = resolved;
+    }
+
+    fn parent_shop(&self, name: &str) -> Option<&ShopConfig> {
+        self.templates
+            .iter()
+            .chain(&self.shops)
+            .find(|shop| shop.name == name)
+    }
+
+    pub fn interpolate(&mut self) -> Result<(), Vec<ConfigError>> {
+        let secrets = &self.secret_values;
+        let mut errors = Vec::new();
+
+        for shop in &mut self.shops {
+            let cookies = shop.cookies.iter_mut().flat_map(|cookies| cookies.values_mut());
+            let sitemaps = shop.sitemaps
+                .iter_mut()
+                .flat_map(|sitemaps| sitemaps)
+                .map(|sitemap| &mut sitemap.url);
+
+            for value in cookies.chain(sitemaps) {
+                interpolate_reporting(value, secrets, &shop.name, &shop.source, &mut errors);
+            }
+        }
+
+        for product in &mut self.products {
+            let cookies = product.cookies.iter_mut().flat_map(|cookies| cookies.values_mut());
+
+            for value in cookies {
+                interpolate_reporting(value, secrets, &product.url, &product.source, &mut errors);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn canonical_url(&self, shop_name: &str, url: &str) -> String {
+        let global_rules = self.canonical.clone().unwrap_or_default();
+        let shop_rules = self.shops
+            .iter()
+            .find(|shop| shop.name == shop_name)
+            .and_then(|shop| shop.canonical.as_ref());
+        let rules = match shop_rules {
+            Some(shop_rules) => global_rules.with_overrides(shop_rules),
+            None => global_rules,
+        };
+
+        canonicalize(url, &rules)
+    }
+
+    pub fn canonicalize(&mut self) {
+        let urls: Vec<_> = self.products
+            .iter()
+            .map(|product| self.canonical_url(&product.shop_name, &product.url))
+            .collect();
+
+        for (product, url) in self.products.iter_mut().zip(urls) {
+            product.url = url;
+        }
+    }
+
+    pub fn validate(&self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        let mut shop_sources = HashMap::new();
+
+        for &(ref key, ref source) in &self.unknown_keys {
+            errors.push(ConfigError::unknown_key(key.clone(), Some(source.clone())));
+        }
+
+        for &(ref section, ref source) in &self.ignored_settings {
+            errors.push(ConfigError::ignored_settings(section.clone(), Some(source.clone())));
+        }
 
         for shop in &self.shops {
             let shop_name = &shop.name;
 
-            if shop_names.contains(shop_name) {
-                return Some(ConfigError::duplicate_shop_name(shop_name.clone()));
+            if let Some(first_source) = shop_sources.get(shop_name) {
+                errors.push(ConfigError::duplicate_shop_name(
+                    shop_name.clone(),
+                    shop.source.clone(),
+                    first_source.clone(),
+                ));
+            } else {
+                shop_sources.insert(shop_name.clone(), shop.source.clone());
+            }
+
+            if shop.name_selector.is_none() {
+                errors.push(ConfigError::missing_selector(
+                    shop_name.clone(),
+                    "name_selector",
+                    shop.source.clone(),
+                ));
+            }
+
+            if shop.price_selector.is_none() {
+                errors.push(ConfigError::missing_selector(
+                    shop_name.clone(),
+                    "price_selector",
+                    shop.source.clone(),
+                ));
+            }
+
+            for sitemap in shop.sitemaps.iter().flat_map(|sitemaps| sitemaps) {
+                if Regex::new(&sitemap.include).is_err() {
+                    errors.push(ConfigError::invalid_sitemap_pattern(
+                        shop_name.clone(),
+                        sitemap.include.clone(),
+                        shop.source.clone(),
+                    ));
+                }
+
+                if !valid_url(&sitemap.url) {
+                    errors.push(ConfigError::invalid_url(sitemap.url.clone(), shop.source.clone()));
+                }
             }
 
-            shop_names.insert(shop_name.clone());
+            if let Some(ref schedule) = shop.schedule {
+                if let Err(description) = Schedule::parse(schedule) {
+                    errors.push(ConfigError::invalid_schedule(
+                        shop_name.clone(),
+                        schedule.clone(),
+                        description,
+                        shop.source.clone(),
+                    ));
+                }
+            }
         }
 
+        let mut template_sources = HashMap::new();
+
+        for template in &self.templates {
+            let template_name = &template.name;
+            let first_source = shop_sources
+                .get(template_name)
+                .or_else(|| template_sources.get(template_name))
+                .cloned();
+
+            if let Some(first_source) = first_source {
+                errors.push(ConfigError::duplicate_shop_name(
+                    template_name.clone(),
+                    template.source.clone(),
+                    first_source,
+                ));
+            } else {
+                template_sources.insert(template_name.clone(), template.source.clone());
+            }
+        }
+
+        for shop in self.shops.iter().chain(&self.templates) {
+            self.validate_inheritance(&mut errors, shop);
+
+            validate_extraction(
+                &mut errors,
+                &shop.name,
+                shop.name_selector.as_ref(),
+                shop.price_selector.as_ref(),
+                shop.price_factor,
+                &shop.source,
+            );
+        }
+
+        let mut product_sources = HashMap::new();
+
         for product in &self.products {
             let shop_name = &product.shop_name;
 
-            if !shop_names.contains(shop_name) {
-                return Some(ConfigError::no_such_shop_exists(shop_name.clone()));
+            if !shop_sources.contains_key(shop_name) {
+                errors.push(ConfigError::no_such_shop_exists(
+                    shop_name.clone(),
+                    product.source.clone(),
+                ));
+            }
+
+            if let Some(first_source) = product_sources.get(&product.url) {
+                errors.push(ConfigError::duplicate_product_url(
+                    product.url.clone(),
+                    product.source.clone(),
+                    first_source.clone(),
+                ));
+            } else {
+                product_sources.insert(product.url.clone(), product.source.clone());
+            }
+
+            if product.url.contains("${") {
+                errors.push(ConfigError::variable_in_url(
+                    product.url.clone(),
+                    product.source.clone(),
+                ));
+            } else if !valid_url(&product.url) {
+                errors.push(ConfigError::invalid_url(product.url.clone(), product.source.clone()));
+            }
+
+            validate_extraction(
+                &mut errors,
+                &product.url,
+                product.name_selector.as_ref(),
+                product.price_selector.as_ref(),
+                product.price_factor,
+                &product.source,
+            );
+
+            if !valid_price_limits(product.min_price, product.max_price, product.max_price_change) {
+                errors.push(ConfigError::invalid_price_limits(
+                    product.url.clone(),
+                    product.source.clone(),
+                ));
+            }
+
+            if let Some(ref schedule) = product.schedule {
+                if let Err(description) = Schedule::parse(schedule) {
+                    errors.push(ConfigError::invalid_schedule(
+                        product.url.clone(),
+                        schedule.clone(),
+                        description,
+                        product.source.clone(),
+                    ));
+                }
             }
         }
 
-        None
+        for category in self.categories.iter().flat_map(|categories| categories) {
+            if !valid_price_limits(
+                category.min_price,
+                category.max_price,
+                category.max_price_change,
+            )
+            {
+                errors.push(ConfigError::invalid_price_limits(
+                    category.name.clone(),
+                    category.source.clone(),
+                ));
+            }
+        }
+
+        errors
+    }
+
+    fn validate_inheritance(&self, errors: &mut Vec<ConfigError>, shop: &ShopConfig) {
+        let mut visited = HashSet::new();
+        let mut parent_name = shop.extends.clone();
+
+        while let Some(name) = parent_name {
+            if !visited.insert(name.clone()) {
+                errors.push(ConfigError::inheritance_cycle(shop.name.clone(), shop.source.clone()));
+
+                return;
+            }
+
+            match self.parent_shop(&name) {
+                Some(parent) => parent_name = parent.extends.clone(),
+                None => {
+                    errors.push(ConfigError::missing_parent(
+                        shop.name.clone(),
+                        name,
+                        shop.source.clone(),
+                    ));
+
+                    return;
+                }
+            }
+        }
+    }
+}
+
+
+fn validate_extraction(
+    errors: &mut Vec<ConfigError>,
+    name: &str,
+    name_selector: Option<&Selector>,
+    price_selector: Option<&Selector>,
+    price_factor: Option<f64>,
+    source: &Option<ConfigSource>,
+) {
+    for selector in name_selector.into_iter().chain(price_selector) {
+        for alternative in selector.alternatives() {
+            if Selectors::compile(alternative).is_err() {
+                errors.push(ConfigError::invalid_selector(name, alternative, source.clone()));
+            }
+        }
+    }
+
+    if let Some(price_factor) = price_factor {
+        if price_factor < 0.0 {
+            errors.push(ConfigError::negative_price_factor(name, source.clone()));
+        }
+    }
+}
+
+fn valid_url(url: &str) -> bool {
+    if url.starts_with(FILE_SCHEMA) {
+        return true;
+    }
+
+    match url.parse::<Uri>() {
+        Ok(uri) => uri.scheme().is_some() && uri.authority().is_some(),
+        Err(_) => false,
+    }
+}
+
+pub fn category_path(name: &str) -> Vec<&str> {
+    name.split('>')
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn valid_price_limits(
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+    max_price_change: Option<f64>,
+) -> bool {
+    let valid_range = match (min_price, max_price) {
+        (Some(min_price), Some(max_price)) => min_price <= max_price,
+        _ => true,
+    };
+    let valid_change = match max_price_change {
+        Some(max_price_change) => max_price_change >= 0.0,
+        None => true,
+    };
+
+    valid_range && valid_change
+}
+
+fn interpolate_reporting(
+    value: &mut String,
+    secrets: &HashMap<String, String>,
+    name: &str,
+    source: &Option<ConfigSource>,
+    errors: &mut Vec<ConfigError>,
+) {
+    if let Err(variables) = interpolate_value(value, secrets) {
+        for variable in variables {
+            errors.push(ConfigError::unresolved_variable(name, variable, source.clone()));
+        }
+    }
+}
+
+fn interpolate_value(
+    value: &mut String,
+    secrets: &HashMap<String, String>,
+) -> Result<(), Vec<String>> {
+    if !value.contains('$') {
+        return Ok(());
+    }
+
+    let mut result = String::new();
+    let mut unresolved = Vec::new();
+    let mut rest = value.as_str();
+
+    while let Some(position) = rest.find('$') {
+        result.push_str(&rest[..position]);
+        rest = &rest[position..];
+
+        if rest.starts_with("$$") {
+            result.push('$');
+            rest = &rest[2..];
+        } else if let (true, Some(end)) = (rest.starts_with("${"), rest.find('}')) {
+            let variable = &rest[2..end];
+            let resolved = secrets.get(variable).cloned().or_else(|| env::var(variable).ok());
+
+            match resolved {
+                Some(resolved) => result.push_str(&resolved),
+                None => unresolved.push(variable.to_string()),
+            }
+
+            rest = &rest[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    if !unresolved.is_empty() {
+        return Err(unresolved);
+    }
+
+    result.push_str(rest);
+    *value = result;
+
+    Ok(())
+}
+
+fn source_name(source: &Option<ConfigSource>) -> String {
+    match *source {
+        Some(ref source) => format!("`{}`", source),
+        None => "unknown file".into(),
     }
 }
@@ -0,0 +1,284 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+
+use shutdown::Shutdown;
+use shutdown::WorkGuard;
+
+use super::config::category_path;
+use super::Config;
+use super::ProductConfig;
+
+
+#[derive(Debug, Clone)]
+pub enum CrawlerEvent {
+    ConfigChanged,
+    Crawl { filter: CrawlFilter },
+    Stop,
+}
+
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrawlFilter {
+    shop: Option<String>,
+    category: Option<String>,
+    product: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct CrawlStatus {
+    running: bool,
+    filter: Option<CrawlFilter>,
+    requests: Vec<CrawlFilter>,
+    processed: usize,
+    total: usize,
+    started: Option<i64>,
+    completed: Option<i64>,
+}
+
+
+#[derive(Debug)]
+pub enum ControlError {
+    CrawlerStopped,
+    NoMatchingProducts,
+    LockError,
+}
+
+
+#[derive(Debug, Clone)]
+struct CrawlTargets {
+    config: Config,
+    products: Vec<ProductConfig>,
+}
+
+
+#[derive(Clone)]
+pub struct CrawlerControl {
+    sender: Arc<Mutex<Sender<CrawlerEvent>>>,
+    status: Arc<Mutex<CrawlStatus>>,
+    targets: Arc<Mutex<Option<CrawlTargets>>>,
+    shutdown: Shutdown,
+}
+
+
+impl CrawlFilter {
+    pub fn new(
+        shop: Option<String>,
+        category: Option<String>,
+        product: Option<String>,
+    ) -> CrawlFilter {
+        CrawlFilter {
+            shop,
+            category,
+            product,
+        }
+    }
+
+    pub fn matches(&self, config: &Config, product: &ProductConfig) -> bool {
+        if let Some(ref shop) = self.shop {
+            if *shop != product.shop_name {
+                return false;
+            }
+        }
+
+        if let Some(ref category) = self.category {
+            if !category_path(&product.category).starts_with(&category_path(category)) {
+                return false;
+            }
+        }
+
+        if let Some(ref url) = self.product {
+            if config.canonical_url(&product.shop_name, url) != product.url {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+
+impl CrawlStatus {
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.requests.len()
+    }
+
+    #[inline]
+    pub fn processed(&self) -> usize {
+        self.processed
+    }
+
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    #[inline]
+    pub fn started(&self) -> Option<i64> {
+        self.started
+    }
+
+    #[inline]
+    pub fn completed(&self) -> Option<i64> {
+        self.completed
+    }
+}
+
+
+impl Display for ControlError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ControlError::CrawlerStopped => write!(f, "Crawler is not running"),
+            ControlError::NoMatchingProducts => write!(f, "No products match requested crawl"),
+            ControlError::LockError => write!(f, "Mutex lock error"),
+        }
+    }
+}
+
+
+impl Error for ControlError {
+    fn description(&self) -> &str {
+        match *self {
+            ControlError::CrawlerStopped => "Crawler is not running",
+            ControlError::NoMatchingProducts => "No products match requested crawl",
+            ControlError::LockError => "Mutex lock error",
+        }
+    }
+}
+
+
+impl CrawlerControl {
+    pub fn new(shutdown: Shutdown) -> (CrawlerControl, Receiver<CrawlerEvent>) {
+        let (sender, receiver) = channel();
+        let control = CrawlerControl {
+            sender: Arc::new(Mutex::new(sender)),
+            status: Arc::new(Mutex::new(CrawlStatus::default())),
+            targets: Arc::new(Mutex::new(None)),
+            shutdown,
+        };
+
+        (control, receiver)
+    }
+
+    pub fn sender(&self) -> Result<Sender<CrawlerEvent>, ControlError> {
+        match self.sender.lock() {
+            Ok(sender) => Ok(sender.clone()),
+            Err(_) => Err(ControlError::LockError),
+        }
+    }
+
+    pub fn request_crawl(&self, filter: CrawlFilter) -> Result<bool, ControlError> {
+        let sender = self.sender()?;
+
+        match *self.targets.lock().map_err(|_| ControlError::LockError)? {
+            Some(ref targets) => {
+                let config = &targets.config;
+
+                if !targets.products.iter().any(|product| filter.matches(config, product)) {
+                    return Err(ControlError::NoMatchingProducts);
+                }
+            }
+            None => {}
+        }
+
+        {
+            let mut status = self.status.lock().map_err(|_| ControlError::LockError)?;
+            if status.filter.as_ref() == Some(&filter) || status.requests.contains(&filter) {
+                return Ok(false);
+            }
+
+            status.requests.push(filter.clone());
+        }
+
+        if sender.send(CrawlerEvent::Crawl { filter: filter.clone() }).is_err() {
+            self.update(|status| status.requests.retain(|request| *request != filter));
+
+            return Err(ControlError::CrawlerStopped);
+        }
+
+        Ok(true)
+    }
+
+    pub fn update_targets(&self, config: &Config, products: &[ProductConfig]) {
+        if let Ok(mut targets) = self.targets.lock() {
+            *targets = Some(CrawlTargets {
+                config: config.clone(),
+                products: products.to_vec(),
+            });
+        }
+    }
+
+    pub fn stop(&self) {
+        if let Ok(sender) = self.sender() {
+            let _ = sender.send(CrawlerEvent::Stop);
+        }
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.shutdown.is_requested()
+    }
+
+    pub fn work(&self) -> WorkGuard {
+        self.shutdown.work()
+    }
+
+    pub fn status(&self) -> CrawlStatus {
+        match self.status.lock() {
+            Ok(status) => status.clone(),
+            Err(_) => CrawlStatus::default(),
+        }
+    }
+
+    pub fn accept_request(&self, filter: &CrawlFilter) {
+        self.update(|status| {
+            status.requests.retain(|request| request != filter);
+            status.filter = Some(filter.clone());
+        });
+    }
+
+    pub fn finish_request(&self) {
+        self.update(|status| status.filter = None);
+    }
+
+    pub fn start_cycle(&self, total: usize, timestamp: i64) {
+        self.update(|status| {
+            status.running = true;
+            status.processed = 0;
+            status.total = total;
+            status.started = Some(timestamp);
+        });
+    }
+
+    pub fn advance(&self) {
+        self.update(|status| status.processed += 1);
+    }
+
+    pub fn complete_cycle(&self, timestamp: i64) {
+        self.update(|status| {
+            status.running = false;
+            status.completed = Some(timestamp);
+        });
+    }
+
+    fn update<F>(&self, action: F)
+    where
+        F: FnOnce(&mut CrawlStatus),
+    {
+        if let Ok(mut status) = self.status.lock() {
+            action(&mut status);
+        }
+    }
+}
@@ -3,7 +3,11 @@ use std::error::Error;
 use std::fmt::Display;
 use std::fmt::Error as FmtError;
 use std::fmt::Formatter;
+use std::fs::File;
 use std::io::Error as IoError;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use futures::Future;
@@ -23,17 +27,51 @@ use native_tls::Error as TlsError;
 use tendril::TendrilSink;
 use tokio_core::reactor::Core;
 use tokio_core::reactor::Handle;
+use url::percent_encoding::percent_decode;
+use url::Url;
 
+use super::cassette::Exchange;
+use super::PricePick;
+use super::PricePickName;
 use super::Product;
 use super::ProductError;
+use super::script::ScriptCache;
+use super::script::ScriptInput;
+use super::Selector;
+use super::ShopConfig;
+
+
+const DEFAULT_SCRIPT_TIMEOUT: u64 = 1000;
+const MAX_REDIRECTS: usize = 5;
+pub const FILE_SCHEMA: &'static str = "file://";
+const FIXTURE_INDEX: &'static str = "index.html";
+
+
+#[derive(Debug, Clone)]
+pub enum LoaderMode {
+    Network,
+    Fixtures { directory: PathBuf },
+    Record { directory: PathBuf },
+    Replay { directory: PathBuf },
+}
 
 
 #[derive(Debug)]
 pub struct PriceLoader {
+    mode: LoaderMode,
     core: Core,
     handle: Handle,
     http_client: Client<HttpConnector, Body>,
     https_client: Client<HttpsConnector<HttpConnector>, Body>,
+    scripts: ScriptCache,
+}
+
+
+#[derive(Debug)]
+pub struct Page {
+    pub status: u16,
+    pub url: String,
+    pub content: Vec<u8>,
 }
 
 
@@ -87,7 +125,7 @@ impl From<TlsError> for PriceLoaderError {
 
 
 impl PriceLoader {
-    pub fn new() -> Result<PriceLoader, PriceLoaderError> {
+    pub fn new(mode: LoaderMode) -> Result<PriceLoader, PriceLoaderError> {
         let core = Core::new()?;
         let handle = core.handle();
         let timeout = Some(Duration::from_secs(60));
@@ -103,23 +141,85 @@ impl PriceLoader {
             .build(&handle);
 
         Ok(PriceLoader {
+            mode,
             core,
             handle,
             http_client,
             https_client,
+            scripts: ScriptCache::new(),
         })
     }
 
-    pub fn load(
+    pub fn load(&mut self, uri: &str, shop: &ShopConfig) -> Result<Product, ProductError> {
+        let page = self.fetch(uri, &shop.cookies)?;
+
+        extract_product(uri, &page.content, shop, &mut self.scripts)
+    }
+
+    pub fn reset_scripts(&mut self) {
+        self.scripts.clear();
+    }
+
+    pub fn fetch(
         &mut self,
         uri: &str,
         cookies: &Option<HashMap<String, String>>,
-        name_selector: &str,
-        price_selector: &str,
-        price_factor: f64,
-        price_index: usize,
-    ) -> Result<Product, ProductError> {
-        let uri: Uri = uri.parse()?;
+    ) -> Result<Page, ProductError> {
+        if uri.starts_with(FILE_SCHEMA) {
+            return read_page(uri, &file_path(uri)?);
+        }
+
+        let mode = self.mode.clone();
+        let origin = Url::parse(uri)?;
+        let no_cookies = None;
+        let mut location: String = uri.into();
+
+        for _ in 0..MAX_REDIRECTS + 1 {
+            let uri: Uri = location.parse()?;
+            let current = Url::parse(&location)?;
+            let cookies = if current.origin() == origin.origin() {
+                cookies
+            } else {
+                &no_cookies
+            };
+            let exchange = match mode {
+                LoaderMode::Network => self.request(&location, cookies)?,
+                LoaderMode::Fixtures { ref directory } => {
+                    return read_page(&location, &fixture_path(directory, &uri))
+                }
+                LoaderMode::Record { ref directory } => {
+                    let exchange = self.request(&location, cookies)?;
+                    exchange.write(directory)?;
+
+                    exchange
+                }
+                LoaderMode::Replay { ref directory } => Exchange::read(directory, &location)?,
+            };
+
+            if exchange.is_redirection() {
+                if let Some(target) = exchange.header("Location") {
+                    location = current.join(target)?.into_string();
+
+                    continue;
+                }
+            }
+
+            return Ok(Page {
+                status: exchange.status,
+                url: location,
+                content: exchange.body,
+            });
+        }
+
+        Err(ProductError::too_many_redirects())
+    }
+
+    fn request(
+        &mut self,
+        url: &str,
+        cookies: &Option<HashMap<String, String>>,
+    ) -> Result<Exchange, ProductError> {
+        let uri: Uri = url.parse()?;
         let schema = match uri.scheme() {
             Some("http") => UriSchema::Http,
             Some("https") => UriSchema::Https,
@@ -141,45 +241,119 @@ impl PriceLoader {
             UriSchema::Http => self.http_client.request(request),
             UriSchema::Https => self.https_client.request(request),
         };
-        let future_content = request.and_then(|res| {
-            res.body().fold(
-                Vec::new(),
-                |mut acc, chunk| -> HyperResult<_> {
-                    acc.extend(chunk);
-
-                    Ok(acc)
-                },
-            )
-        });
-        let content = self.core.run(future_content)?;
-        let document = parse_html().from_utf8().one(content.as_slice());
-        let product_name = query_name(&document, name_selector)?.ok_or_else(
-            ProductError::name_not_found,
-        )?;
-        let product_price: u64 = query_price(&document, price_selector, price_index)?
-            .ok_or_else(ProductError::price_not_found)?
-            .parse()?;
-
-        Ok(Product::new(
-            product_name,
-            price_factor * product_price as f64,
-        ))
-    }
-}
-
-fn query_name(document: &NodeRef, selector: &str) -> Result<Option<String>, ProductError> {
-    for css_match in document.select(selector).map_err(
-        ProductError::name_not_exists,
-    )?
-    {
-        let children = css_match.as_node().children();
-
-        for child in children {
-            if let Some(text) = child.as_text() {
-                let text: String = text.borrow().trim().into();
-
-                if !text.is_empty() {
-                    return Ok(Some(text));
+        let response = self.core.run(request)?;
+        let status = u16::from(response.status());
+        let headers = response
+            .headers()
+            .iter()
+            .map(|header| (header.name().to_string(), header.value_string()))
+            .collect();
+        let future_content = response.body().fold(
+            Vec::new(),
+            |mut acc, chunk| -> HyperResult<_> {
+                acc.extend(chunk);
+
+                Ok(acc)
+            },
+        );
+        let body = self.core.run(future_content)?;
+
+        Ok(Exchange {
+            url: url.into(),
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+pub fn extract_product(
+    uri: &str,
+    content: &[u8],
+    shop: &ShopConfig,
+    scripts: &mut ScriptCache,
+) -> Result<Product, ProductError> {
+    let document = parse_html().from_utf8().one(content);
+    let name = match shop.name_selector {
+        Some(ref selector) => query_name(&document, selector)?,
+        None => None,
+    };
+    let price = match shop.price_selector {
+        Some(ref selector) => {
+            query_price(&document, selector, shop.price_pick, shop.price_index.unwrap_or(0))?
+        }
+        None => None,
+    };
+    let price_factor = shop.price_factor.unwrap_or(1.0);
+    let name_alternative = name.as_ref().map(|&(alternative, _)| alternative).unwrap_or(0);
+    let price_alternative = price
+        .as_ref()
+        .map(|&(alternative, _, _)| alternative)
+        .unwrap_or(0);
+
+    if let Some(ref script) = shop.script {
+        let timeout = shop.script_timeout.unwrap_or(DEFAULT_SCRIPT_TIMEOUT);
+        let input = ScriptInput {
+            url: uri.into(),
+            name: name.map(|(_, name)| name),
+            price: match price {
+                Some((_, ref text, _)) => parse_price(text).ok().map(
+                    |price| price_factor * price as f64,
+                ),
+                None => None,
+            },
+            price_texts: match price {
+                Some((_, _, ref texts)) => texts.clone(),
+                None => Vec::new(),
+            },
+            price_text: price.map(|(_, text, _)| text),
+        };
+        let output = scripts.run(script, Duration::from_millis(timeout), &input)?;
+
+        if !output.available {
+            return Err(ProductError::not_available());
+        }
+
+        return Ok(Product::new(
+            output.name.ok_or_else(ProductError::name_not_found)?,
+            output.price.ok_or_else(ProductError::price_not_found)?,
+            input.price_text,
+            name_alternative,
+            price_alternative,
+        ));
+    }
+
+    let product_name = name.ok_or_else(ProductError::name_not_found)?.1;
+    let price_text = price.ok_or_else(ProductError::price_not_found)?.1;
+    let product_price = parse_price(&price_text)?;
+
+    Ok(Product::new(
+        product_name,
+        price_factor * product_price as f64,
+        Some(price_text),
+        name_alternative,
+        price_alternative,
+    ))
+}
+
+fn query_name(
+    document: &NodeRef,
+    selector: &Selector,
+) -> Result<Option<(usize, String)>, ProductError> {
+    for (alternative, selector) in selector.alternatives().into_iter().enumerate() {
+        for css_match in document.select(selector).map_err(
+            ProductError::name_not_exists,
+        )?
+        {
+            let children = css_match.as_node().children();
+
+            for child in children {
+                if let Some(text) = child.as_text() {
+                    let text: String = text.borrow().trim().into();
+
+                    if !text.is_empty() {
+                        return Ok(Some((alternative, text)));
+                    }
                 }
             }
         }
@@ -190,28 +364,138 @@ fn query_name(document: &NodeRef, selector: &str) -> Result<Option<String>, Prod
 
 fn query_price(
     document: &NodeRef,
-    selector: &str,
+    selector: &Selector,
+    pick: Option<PricePick>,
     index: usize,
-) -> Result<Option<String>, ProductError> {
-    for css_match in document
-        .select(selector)
-        .map_err(ProductError::price_not_exists)?
-        .enumerate()
-        .filter(|&(i, _)| i == index)
-        .map(|(_, value)| value)
-    {
-        let children = css_match.as_node().children();
-
-        for child in children {
-            if let Some(text) = child.as_text() {
-                let text: String = text.borrow().chars().filter(|c| c.is_digit(10)).collect();
-
-                if !text.is_empty() {
-                    return Ok(Some(text));
-                }
+) -> Result<Option<(usize, String, Vec<String>)>, ProductError> {
+    for (alternative, selector) in selector.alternatives().into_iter().enumerate() {
+        let mut texts = Vec::new();
+        let mut indexed = None;
+
+        for (position, css_match) in document
+            .select(selector)
+            .map_err(ProductError::price_not_exists)?
+            .enumerate()
+        {
+            let text = css_match
+                .as_node()
+                .children()
+                .filter_map(|child| child.as_text().map(|text| text.borrow().trim().to_string()))
+                .find(|text| text.chars().any(|c| c.is_digit(10)));
+
+            if position == index {
+                indexed = text.clone();
             }
+
+            texts.extend(text);
+        }
+
+        let text = match pick {
+            Some(pick) => pick_price(&texts, pick),
+            None => indexed,
+        };
+
+        if let Some(text) = text {
+            return Ok(Some((alternative, text, texts)));
         }
     }
 
     Ok(None)
 }
+
+fn pick_price(texts: &[String], pick: PricePick) -> Option<String> {
+    let length = texts.len() as isize;
+    let index = match pick {
+        PricePick::Index(index) if index < 0 => length + index,
+        PricePick::Index(index) => index,
+        PricePick::Named(PricePickName::First) => 0,
+        PricePick::Named(PricePickName::Last) => length - 1,
+        PricePick::Named(PricePickName::Min) => {
+            return texts
+                .iter()
+                .filter_map(|text| parse_price(text).ok().map(|price| (price, text)))
+                .min_by_key(|&(price, _)| price)
+                .map(|(_, text)| text.clone())
+        }
+        PricePick::Named(PricePickName::Max) => {
+            return texts
+                .iter()
+                .filter_map(|text| parse_price(text).ok().map(|price| (price, text)))
+                .max_by_key(|&(price, _)| price)
+                .map(|(_, text)| text.clone())
+        }
+    };
+
+    if index >= 0 && index < length {
+        Some(texts[index as usize].clone())
+    } else {
+        None
+    }
+}
+
+fn parse_price(text: &str) -> Result<u64, ProductError> {
+    let digits: String = text.chars().filter(|c| c.is_digit(10)).collect();
+
+    Ok(digits.parse()?)
+}
+
+fn file_path(uri: &str) -> Result<PathBuf, ProductError> {
+    let url = Url::parse(uri)?;
+
+    match url.host_str() {
+        None | Some("localhost") => url.to_file_path().map_err(|_| ProductError::InvaliudUri),
+        Some(_) => {
+            let path = percent_decode(uri[FILE_SCHEMA.len()..].as_bytes()).decode_utf8_lossy();
+
+            Ok(PathBuf::from(path.into_owned()))
+        }
+    }
+}
+
+fn read_page(uri: &str, path: &Path) -> Result<Page, ProductError> {
+    let mut file = File::open(path)?;
+    let mut content = Vec::new();
+
+    file.read_to_end(&mut content)?;
+
+    Ok(Page {
+        status: 200,
+        url: uri.into(),
+        content,
+    })
+}
+
+fn fixture_path(directory: &Path, uri: &Uri) -> PathBuf {
+    let mut result = directory.join(sanitize(uri.authority().unwrap_or("")));
+
+    for segment in uri.path().split('/') {
+        if !segment.is_empty() && segment != "." && segment != ".." {
+            result.push(sanitize(segment));
+        }
+    }
+
+    if uri.path().ends_with('/') {
+        result.push(FIXTURE_INDEX);
+    }
+
+    if let Some(query) = uri.query() {
+        let file_name = match result.file_name() {
+            Some(file_name) => format!("{}_{}", file_name.to_string_lossy(), sanitize(query)),
+            None => sanitize(query),
+        };
+
+        result.set_file_name(file_name);
+    }
+
+    result
+}
+
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() || c == '.' || c == '-' || c == '_' {
+            c
+        } else {
+            '_'
+        })
+        .collect()
+}
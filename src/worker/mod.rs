@@ -1,12 +1,52 @@
+mod canonical;
+mod cassette;
+mod check;
 mod config;
+mod control;
 mod loader;
+mod outline;
 mod product;
+mod reader;
+mod sanity;
+mod schedule;
+mod script;
+mod sitemap;
 mod starter;
+mod watcher;
 
+pub use self::check::check_product;
+pub use self::check::CheckError;
+pub use self::config::CanonicalConfig;
+pub use self::config::category_path;
+pub use self::config::CategoryConfig;
 pub use self::config::Config;
 pub use self::config::ConfigError;
+pub use self::config::ConfigSource;
+pub use self::config::CrawlerConfig;
+pub use self::config::PricePick;
+pub use self::config::PricePickName;
+pub use self::config::ProductConfig;
+pub use self::config::Selector;
+pub use self::config::ServerConfig;
+pub use self::config::SettingsConfig;
+pub use self::config::ShopConfig;
+pub use self::config::SitemapConfig;
+pub use self::config::TrailingSlash;
+pub use self::control::ControlError;
+pub use self::control::CrawlFilter;
+pub use self::control::CrawlStatus;
+pub use self::control::CrawlerControl;
+pub use self::loader::extract_product;
+pub use self::loader::LoaderMode;
+pub use self::loader::Page;
 pub use self::loader::PriceLoader;
 pub use self::loader::PriceLoaderError;
 pub use self::product::Product;
 pub use self::product::ProductError;
+pub use self::reader::load_config;
+pub use self::reader::read_config;
+pub use self::reader::read_settings;
+pub use self::reader::ReadConfigError;
+pub use self::reader::validate_config;
+pub use self::starter::crawl_once;
 pub use self::starter::start_crawler;
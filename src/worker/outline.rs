@@ -0,0 +1,339 @@
+use std::cmp;
+use std::collections::HashMap;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutlineFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+
+#[derive(Debug, Clone)]
+struct Section {
+    start: usize,
+    end: usize,
+    items: Vec<(usize, usize)>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Outline {
+    format: OutlineFormat,
+    lines: Vec<String>,
+    sections: HashMap<String, Section>,
+}
+
+
+impl Section {
+    fn new(start: usize) -> Section {
+        Section {
+            start,
+            end: start + 1,
+            items: Vec::new(),
+        }
+    }
+
+    fn close(&mut self, end: usize) {
+        self.end = cmp::max(self.end, end);
+        self.close_item(end);
+    }
+
+    fn add_item(&mut self, line: usize) {
+        self.close_item(line);
+        self.items.push((line, 0));
+    }
+
+    fn close_item(&mut self, end: usize) {
+        if let Some(item) = self.items.last_mut() {
+            if item.1 == 0 {
+                item.1 = end;
+            }
+        }
+    }
+}
+
+
+impl Outline {
+    pub fn new(format: &str, content: &str) -> Outline {
+        let format = match format {
+            "yaml" | "yml" => OutlineFormat::Yaml,
+            "json" => OutlineFormat::Json,
+            _ => OutlineFormat::Toml,
+        };
+        let lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+        let sections = match format {
+            OutlineFormat::Toml => toml_sections(&lines),
+            OutlineFormat::Yaml => yaml_sections(&lines),
+            OutlineFormat::Json => json_sections(content, lines.len()),
+        };
+
+        Outline {
+            format,
+            lines,
+            sections,
+        }
+    }
+
+    pub fn item_line(&self, section: &str, index: usize) -> Option<usize> {
+        self.sections
+            .get(section)
+            .and_then(|section| section.items.get(index))
+            .map(|&(start, _)| start)
+    }
+
+    pub fn key_line(&self, path: &str) -> Option<usize> {
+        let parts: Vec<_> = path.split('.').filter(|part| *part != "?").collect();
+        let section = self.sections.get(*parts.first()?)?;
+        let (start, end) = match parts.get(1).and_then(|part| part.parse::<usize>().ok()) {
+            Some(index) => *section.items.get(index)?,
+            None => (section.start, section.end),
+        };
+        let key = parts
+            .iter()
+            .skip(1)
+            .rev()
+            .find(|part| part.parse::<usize>().is_err());
+
+        match key {
+            Some(key) => Some(self.find_key(key, start, end).unwrap_or(start)),
+            None => Some(start),
+        }
+    }
+
+    fn find_key(&self, key: &str, start: usize, end: usize) -> Option<usize> {
+        (start..end).find(|&line| {
+            self.lines
+                .get(line - 1)
+                .map_or(false, |text| line_has_key(self.format, text, key))
+        })
+    }
+}
+
+
+fn line_has_key(format: OutlineFormat, text: &str, key: &str) -> bool {
+    let text = text.trim();
+
+    match format {
+        OutlineFormat::Toml => key_followed_by(text, key, '='),
+        OutlineFormat::Yaml => {
+            key_followed_by(text.trim_matches(|c| c == '-' || c == ' '), key, ':')
+        }
+        OutlineFormat::Json => {
+            let quoted = format!("\"{}\"", key);
+
+            text.match_indices(&quoted as &str).any(|(index, _)| {
+                text[index + quoted.len()..].trim().starts_with(':')
+            })
+        }
+    }
+}
+
+
+fn key_followed_by(text: &str, key: &str, separator: char) -> bool {
+    let candidates = [key.to_string(), format!("\"{}\"", key), format!("'{}'", key)];
+
+    candidates.iter().any(|candidate| {
+        text.starts_with(candidate as &str) &&
+            text[candidate.len()..].trim().starts_with(separator)
+    })
+}
+
+
+fn indent(text: &str) -> Option<usize> {
+    text.find(|c: char| !c.is_whitespace())
+}
+
+
+fn toml_sections(lines: &[String]) -> HashMap<String, Section> {
+    let mut sections: HashMap<String, Section> = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut tables = false;
+
+    for (index, text) in lines.iter().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+
+        if text.starts_with('[') {
+            tables = true;
+
+            let array = text.starts_with("[[");
+            let name = text.trim_matches(|c| c == '[' || c == ']').trim().to_string();
+            let parent = name.split('.').next().unwrap_or("").to_string();
+
+            if parent != name && current.as_ref() == Some(&parent) {
+                continue;
+            }
+
+            if let Some(current) = current.take() {
+                if let Some(section) = sections.get_mut(&current) {
+                    section.close(line);
+                }
+            }
+
+            if parent != name {
+                continue;
+            }
+
+            let section = sections.entry(name.clone()).or_insert_with(|| Section::new(line));
+
+            if array {
+                section.add_item(line);
+            }
+
+            current = Some(name);
+        } else if !tables {
+            if let Some(position) = text.find('=') {
+                let name = text[..position].trim().trim_matches(|c| c == '"' || c == '\'');
+
+                if !name.is_empty() && !text.starts_with('#') {
+                    sections.insert(name.to_string(), Section::new(line));
+                }
+            }
+        }
+    }
+
+    if let Some(current) = current {
+        if let Some(section) = sections.get_mut(&current) {
+            section.close(lines.len() + 1);
+        }
+    }
+
+    sections
+}
+
+
+fn yaml_sections(lines: &[String]) -> HashMap<String, Section> {
+    let mut sections: HashMap<String, Section> = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut item_indent = None;
+
+    for (index, text) in lines.iter().enumerate() {
+        let line = index + 1;
+        let indent = match indent(text) {
+            Some(indent) => indent,
+            None => continue,
+        };
+        let text = text.trim();
+
+        if text.starts_with('#') || text == "---" {
+            continue;
+        }
+
+        if indent == 0 && !text.starts_with('-') {
+            if let Some(current) = current.take() {
+                if let Some(section) = sections.get_mut(&current) {
+                    section.close(line);
+                }
+            }
+
+            if let Some(position) = text.find(':') {
+                let name = text[..position].trim_matches(|c| c == '"' || c == '\'').to_string();
+
+                sections.insert(name.clone(), Section::new(line));
+                current = Some(name);
+                item_indent = None;
+            }
+
+            continue;
+        }
+
+        if text == "-" || text.starts_with("- ") {
+            if item_indent.is_none() {
+                item_indent = Some(indent);
+            }
+
+            if item_indent == Some(indent) {
+                if let Some(section) = current.as_ref().and_then(|name| sections.get_mut(name)) {
+                    section.add_item(line);
+                }
+            }
+        }
+    }
+
+    if let Some(current) = current {
+        if let Some(section) = sections.get_mut(&current) {
+            section.close(lines.len() + 1);
+        }
+    }
+
+    sections
+}
+
+
+fn json_sections(content: &str, line_count: usize) -> HashMap<String, Section> {
+    let mut sections: HashMap<String, Section> = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut containers = Vec::new();
+    let mut line = 1;
+    let mut in_string = false;
+    let mut escape = false;
+    let mut text = String::new();
+    let mut last_string = None;
+
+    for c in content.chars() {
+        if in_string {
+            if escape {
+                escape = false;
+                text.push(c);
+            } else if c == '\\' {
+                escape = true;
+            } else if c == '"' {
+                in_string = false;
+                last_string = Some((text.clone(), line));
+            } else {
+                text.push(c);
+            }
+
+            if c == '\n' {
+                line += 1;
+            }
+
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                text.clear();
+            }
+            ':' if containers.len() == 1 => {
+                if let Some((name, start)) = last_string.take() {
+                    if let Some(current) = current.take() {
+                        if let Some(section) = sections.get_mut(&current) {
+                            section.close(start);
+                        }
+                    }
+
+                    sections.insert(name.clone(), Section::new(start));
+                    current = Some(name);
+                }
+            }
+            '{' | '[' => {
+                if c == '{' && containers == ['{', '['] {
+                    let section = current.as_ref().and_then(|name| sections.get_mut(name));
+
+                    if let Some(section) = section {
+                        section.add_item(line);
+                    }
+                }
+
+                containers.push(c);
+            }
+            '}' | ']' => {
+                containers.pop();
+            }
+            '\n' => line += 1,
+            _ => {}
+        }
+    }
+
+    if let Some(current) = current {
+        if let Some(section) = sections.get_mut(&current) {
+            section.close(line_count + 1);
+        }
+    }
+
+    sections
+}
@@ -2,16 +2,21 @@ use std::error::Error;
 use std::fmt::Display;
 use std::fmt::Error as FmtError;
 use std::fmt::Formatter;
+use std::io::Error as IoError;
 use std::num::ParseIntError;
 
 use hyper::Error as HyperError;
 use hyper::error::UriError;
+use url::ParseError as UrlError;
 
 
 #[derive(Debug, Clone)]
 pub struct Product {
     pub name: String,
     pub price: f64,
+    pub price_text: Option<String>,
+    pub name_alternative: usize,
+    pub price_alternative: usize,
 }
 
 
@@ -25,6 +30,10 @@ pub enum ProductError {
     NameNotFound,
     PriceNotFound,
     ParsePriceError,
+    ScriptError { description: String },
+    NotAvailable,
+    TooManyRedirects,
+    CassetteError { description: String },
 }
 
 
@@ -53,6 +62,32 @@ impl ProductError {
     pub fn price_not_found() -> ProductError {
         ProductError::PriceNotFound
     }
+
+    #[inline]
+    pub fn script_error<S>(description: S) -> ProductError
+    where
+        S: Into<String>,
+    {
+        ProductError::ScriptError { description: description.into() }
+    }
+
+    #[inline]
+    pub fn not_available() -> ProductError {
+        ProductError::NotAvailable
+    }
+
+    #[inline]
+    pub fn too_many_redirects() -> ProductError {
+        ProductError::TooManyRedirects
+    }
+
+    #[inline]
+    pub fn cassette_error<S>(description: S) -> ProductError
+    where
+        S: Into<String>,
+    {
+        ProductError::CassetteError { description: description.into() }
+    }
 }
 
 
@@ -63,6 +98,13 @@ impl From<UriError> for ProductError {
 }
 
 
+impl From<UrlError> for ProductError {
+    fn from(_: UrlError) -> ProductError {
+        ProductError::InvaliudUri
+    }
+}
+
+
 impl From<HyperError> for ProductError {
     fn from(error: HyperError) -> ProductError {
         ProductError::IoError { description: error.description().into() }
@@ -70,6 +112,13 @@ impl From<HyperError> for ProductError {
 }
 
 
+impl From<IoError> for ProductError {
+    fn from(error: IoError) -> ProductError {
+        ProductError::IoError { description: error.description().into() }
+    }
+}
+
+
 impl From<ParseIntError> for ProductError {
     fn from(_: ParseIntError) -> ProductError {
         ProductError::ParsePriceError
@@ -88,6 +137,14 @@ impl Display for ProductError {
             ProductError::NameNotFound => write!(f, "Name not found on a page"),
             ProductError::PriceNotFound => write!(f, "Price not found on a page"),
             ProductError::ParsePriceError => write!(f, "Price has non numeric format"),
+            ProductError::ScriptError { ref description } => {
+                write!(f, "Script error: {}", description)
+            }
+            ProductError::NotAvailable => write!(f, "Product is not available"),
+            ProductError::TooManyRedirects => write!(f, "Too many redirects"),
+            ProductError::CassetteError { ref description } => {
+                write!(f, "Cassette error: {}", description)
+            }
         }
     }
 }
@@ -104,13 +161,29 @@ impl Error for ProductError {
             ProductError::NameNotFound => "Name not found on a page",
             ProductError::PriceNotFound => "Price not found on a page",
             ProductError::ParsePriceError => "Price has non numeric format",
+            ProductError::ScriptError { .. } => "Script error",
+            ProductError::NotAvailable => "Product is not available",
+            ProductError::TooManyRedirects => "Too many redirects",
+            ProductError::CassetteError { .. } => "Cassette error",
         }
     }
 }
 
 
 impl Product {
-    pub fn new(name: String, price: f64) -> Product {
-        Product { name, price }
+    pub fn new(
+        name: String,
+        price: f64,
+        price_text: Option<String>,
+        name_alternative: usize,
+        price_alternative: usize,
+    ) -> Product {
+        Product {
+            name,
+            price,
+            price_text,
+            name_alternative,
+            price_alternative,
+        }
     }
 }
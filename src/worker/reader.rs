@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::fs::File;
+use std::io::Error as IoError;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+
+use glob::glob;
+use serde::de::DeserializeOwned;
+use serde_ignored;
+use serde_json::Deserializer as JsonDeserializer;
+use serde_json::Error as JsonError;
+use serde_json;
+use serde_yaml::Error as YamlError;
+use serde_yaml::Value as YamlValue;
+use serde_yaml;
+use toml::de::Deserializer as TomlDeserializer;
+use toml::de::Error as TomlError;
+use toml;
+
+use super::Config;
+use super::ConfigError;
+use super::ConfigSource;
+use super::outline::Outline;
+use super::SettingsConfig;
+
+
+const GLOB_CHARACTERS: &'static [char] = &['*', '?', '['];
+
+
+#[derive(Debug, Clone)]
+pub enum ReadConfigError {
+    IoError { file: PathBuf, description: String },
+    ParsingError {
+        file: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    InvalidInclude { file: PathBuf, pattern: String },
+    InvalidConfig { problems: Vec<ConfigError> },
+}
+
+
+impl ReadConfigError {
+    fn io_error(file: &Path, error: IoError) -> ReadConfigError {
+        ReadConfigError::IoError {
+            file: file.to_path_buf(),
+            description: error.description().into(),
+        }
+    }
+
+    fn parsing_error(file: &Path, line: Option<usize>, column: Option<usize>) -> ReadConfigError {
+        ReadConfigError::ParsingError {
+            file: file.to_path_buf(),
+            line,
+            column,
+        }
+    }
+
+    fn toml_error(file: &Path, error: TomlError) -> ReadConfigError {
+        // TOML parser reports zero based position unlike other parsers.
+        ReadConfigError::parsing_error(
+            file,
+            error.line_col().map(|(line, _)| line + 1),
+            error.line_col().map(|(_, column)| column + 1),
+        )
+    }
+
+    fn yaml_error(file: &Path, error: YamlError) -> ReadConfigError {
+        ReadConfigError::parsing_error(
+            file,
+            error.location().map(|location| location.line()),
+            error.location().map(|location| location.column()),
+        )
+    }
+
+    fn json_error(file: &Path, error: JsonError) -> ReadConfigError {
+        ReadConfigError::parsing_error(file, Some(error.line()), Some(error.column()))
+    }
+
+    fn invalid_include<S>(file: &Path, pattern: S) -> ReadConfigError
+    where
+        S: Into<String>,
+    {
+        ReadConfigError::InvalidInclude {
+            file: file.to_path_buf(),
+            pattern: pattern.into(),
+        }
+    }
+
+    fn invalid_config(problems: Vec<ConfigError>) -> ReadConfigError {
+        ReadConfigError::InvalidConfig { problems }
+    }
+}
+
+
+impl Display for ReadConfigError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ReadConfigError::IoError {
+                ref file,
+                ref description,
+            } => write!(f, "IO error in `{}`: {}", file.display(), description),
+            ReadConfigError::ParsingError {
+                ref file,
+                line: Some(line),
+                column: Some(column),
+            } => {
+                write!(
+                    f,
+                    "Parsing error in `{}` at line {} column {}",
+                    file.display(),
+                    line,
+                    column
+                )
+            }
+            ReadConfigError::ParsingError {
+                ref file,
+                line: Some(line),
+                column: None,
+            } => write!(f, "Parsing error in `{}` at line {}", file.display(), line),
+            ReadConfigError::ParsingError {
+                ref file,
+                line: None,
+                column: Some(column),
+            } => write!(f, "Parsing error in `{}` at column {}", file.display(), column),
+            ReadConfigError::ParsingError {
+                ref file,
+                line: None,
+                column: None,
+            } => write!(f, "Parsing error in `{}`", file.display()),
+            ReadConfigError::InvalidInclude {
+                ref file,
+                ref pattern,
+            } => write!(f, "Invalid include `{}` in `{}`", pattern, file.display()),
+            ReadConfigError::InvalidConfig { ref problems } => {
+                let problems: Vec<_> =
+                    problems.iter().map(|problem| format!("{}", problem)).collect();
+
+                write!(f, "Invalid configuration: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+
+impl Error for ReadConfigError {
+    fn description(&self) -> &str {
+        match *self {
+            ReadConfigError::IoError { .. } => "IO error",
+            ReadConfigError::ParsingError { .. } => "Parsing error",
+            ReadConfigError::InvalidInclude { .. } => "Invalid include",
+            ReadConfigError::InvalidConfig { .. } => "Invalid configuration",
+        }
+    }
+}
+
+
+pub fn read_config<P>(path: P) -> Result<Config, ReadConfigError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut config = load_config(path)?;
+
+    info!("Validating configuration from file `{}`.", path.display());
+
+    let (warnings, errors): (Vec<_>, Vec<_>) = validate_config(&config)
+        .into_iter()
+        .partition(|problem| problem.is_warning());
+
+    for warning in warnings {
+        warn!("Configuration warning: {}", warning);
+    }
+
+    if errors.is_empty() {
+        config.remove_duplicate_products();
+
+        Ok(config)
+    } else {
+        Err(ReadConfigError::invalid_config(errors))
+    }
+}
+
+
+pub fn load_config<P>(path: P) -> Result<Config, ReadConfigError>
+where
+    P: AsRef<Path>,
+{
+    let mut config = Config::default();
+    let mut visited = HashSet::new();
+
+    read_config_file(path.as_ref(), &mut config, &mut visited)?;
+    config.resolve_inheritance();
+
+    if let Err(errors) = config.interpolate() {
+        return Err(ReadConfigError::invalid_config(errors));
+    }
+
+    config.canonicalize();
+
+    Ok(config)
+}
+
+
+pub fn read_settings<P>(path: P) -> Result<SettingsConfig, ReadConfigError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let content = read_file(path)?;
+
+    parse_file(path, &content)
+}
+
+
+pub fn validate_config(config: &Config) -> Vec<ConfigError> {
+    config.validate()
+}
+
+
+fn read_config_file(
+    path: &Path,
+    config: &mut Config,
+    visited: &mut HashSet<PathBuf>,
+) -> Result<(), ReadConfigError> {
+    let path = path.canonicalize().map_err(
+        |error| ReadConfigError::io_error(path, error),
+    )?;
+
+    let included = !visited.is_empty();
+
+    if !visited.insert(path.clone()) {
+        info!("File `{}` already read, skipped.", path.display());
+
+        return Ok(());
+    }
+
+    let content = read_file(&path)?;
+
+    info!("Parsing configuration from file `{}`.", path.display());
+
+    let (mut part, unknown_keys) = parse_config(&path, &content)?;
+    let outline = Outline::new(&file_format(&path), &content);
+    let includes = part.include.take().unwrap_or_default();
+    let secrets = part.secrets.take();
+    let directory = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+
+    part.set_source(&path, &outline);
+    part.resolve_scripts(&directory);
+    part.unknown_keys = unknown_keys
+        .into_iter()
+        .map(|key| {
+            let source = ConfigSource::new(&path, outline.key_line(&key));
+
+            (key, source)
+        })
+        .collect();
+
+    if included {
+        if part.server.is_some() {
+            let source = ConfigSource::new(&path, outline.key_line("server"));
+
+            part.ignored_settings.push(("server".into(), source));
+        }
+
+        if part.crawler.is_some() {
+            let source = ConfigSource::new(&path, outline.key_line("crawler"));
+
+            part.ignored_settings.push(("crawler".into(), source));
+        }
+    }
+
+    config.merge(part);
+
+    if let Some(secrets) = secrets {
+        read_secrets(&directory.join(secrets), config)?;
+    }
+
+    for include in includes {
+        let pattern = directory.join(&include);
+
+        if !include.contains(GLOB_CHARACTERS) {
+            read_config_file(&pattern, config, visited)?;
+
+            continue;
+        }
+
+        if let Some(include_directory) = pattern.parent() {
+            if !include_directory.to_string_lossy().contains(GLOB_CHARACTERS) {
+                if let Ok(include_directory) = include_directory.canonicalize() {
+                    config.include_directories.push(include_directory);
+                }
+            }
+        }
+
+        let entries = glob(&pattern.to_string_lossy()).map_err(|_| {
+            ReadConfigError::invalid_include(&path, include.clone())
+        })?;
+        let mut files: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.is_file())
+            .collect();
+
+        files.sort();
+
+        for file in files {
+            read_config_file(&file, config, visited)?;
+        }
+    }
+
+    Ok(())
+}
+
+
+fn read_secrets(path: &Path, config: &mut Config) -> Result<(), ReadConfigError> {
+    let path = path.canonicalize().map_err(
+        |error| ReadConfigError::io_error(path, error),
+    )?;
+    let content = read_file(&path)?;
+    let secrets: HashMap<String, String> = parse_file(&path, &content)?;
+
+    config.secret_values.extend(secrets);
+    config.sources.push(path);
+
+    Ok(())
+}
+
+
+fn read_file(path: &Path) -> Result<String, ReadConfigError> {
+    info!("Reading file `{}`.", path.display());
+
+    let mut file = File::open(path).map_err(
+        |error| ReadConfigError::io_error(path, error),
+    )?;
+    let mut content = String::new();
+
+    file.read_to_string(&mut content).map_err(|error| {
+        ReadConfigError::io_error(path, error)
+    })?;
+
+    Ok(content)
+}
+
+
+fn file_format(path: &Path) -> String {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn parse_file<T>(path: &Path, content: &str) -> Result<T, ReadConfigError>
+where
+    T: DeserializeOwned,
+{
+    match file_format(path).as_str() {
+        "yaml" | "yml" => serde_yaml::from_str(content).map_err(
+            |error| ReadConfigError::yaml_error(path, error),
+        ),
+        "json" => serde_json::from_str(content).map_err(
+            |error| ReadConfigError::json_error(path, error),
+        ),
+        _ => toml::from_str(content).map_err(|error| ReadConfigError::toml_error(path, error)),
+    }
+}
+
+fn parse_config(path: &Path, content: &str) -> Result<(Config, Vec<String>), ReadConfigError> {
+    let mut unknown_keys = Vec::new();
+    let config = {
+        let unknown_key = |key: serde_ignored::Path| unknown_keys.push(key.to_string());
+
+        match file_format(path).as_str() {
+            "yaml" | "yml" => {
+                let config = serde_yaml::from_str(content).map_err(
+                    |error| ReadConfigError::yaml_error(path, error),
+                )?;
+                let value: YamlValue = serde_yaml::from_str(content).map_err(
+                    |error| ReadConfigError::yaml_error(path, error),
+                )?;
+                let _: Config = serde_ignored::deserialize(value, unknown_key).map_err(
+                    |error| ReadConfigError::yaml_error(path, error),
+                )?;
+
+                config
+            }
+            "json" => {
+                let mut deserializer = JsonDeserializer::from_str(content);
+                let config = serde_ignored::deserialize(&mut deserializer, unknown_key).map_err(
+                    |error| ReadConfigError::json_error(path, error),
+                )?;
+
+                deserializer.end().map_err(
+                    |error| ReadConfigError::json_error(path, error),
+                )?;
+
+                config
+            }
+            _ => {
+                let mut deserializer = TomlDeserializer::new(content);
+                let config = serde_ignored::deserialize(&mut deserializer, unknown_key).map_err(
+                    |error| ReadConfigError::toml_error(path, error),
+                )?;
+
+                deserializer.end().map_err(
+                    |error| ReadConfigError::toml_error(path, error),
+                )?;
+
+                config
+            }
+        }
+    };
+
+    Ok((config, unknown_keys))
+}
+
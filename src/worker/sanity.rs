@@ -0,0 +1,111 @@
+use std::f64;
+
+use database::PriceReading;
+
+use super::CategoryConfig;
+use super::ProductConfig;
+
+
+#[derive(Debug, Clone, Default)]
+pub struct PriceLimits {
+    min_price: Option<f64>,
+    max_price: Option<f64>,
+    max_price_change: Option<f64>,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum PriceVerdict {
+    Accepted,
+    Confirmed,
+    Quarantined { reason: String },
+}
+
+
+impl PriceLimits {
+    pub fn new(product: &ProductConfig, category: Option<&CategoryConfig>) -> PriceLimits {
+        let mut limits = PriceLimits::default();
+
+        if let Some(category) = category {
+            limits.min_price = category.min_price;
+            limits.max_price = category.max_price;
+            limits.max_price_change = category.max_price_change;
+        }
+
+        limits.min_price = product.min_price.or(limits.min_price);
+        limits.max_price = product.max_price.or(limits.max_price);
+        limits.max_price_change = product.max_price_change.or(limits.max_price_change);
+        limits
+    }
+
+    pub fn check(
+        &self,
+        price: f64,
+        last_accepted: Option<f64>,
+        last_reading: Option<&PriceReading>,
+    ) -> PriceVerdict {
+        if let Some(reason) = self.range_violation(price) {
+            return PriceVerdict::quarantined(reason);
+        }
+
+        if let (Some(max_price_change), Some(last_price)) = (self.max_price_change, last_accepted) {
+            if relative_change(last_price, price) > max_price_change {
+                let confirmed = match last_reading {
+                    Some(reading) => {
+                        reading.is_quarantined() &&
+                            self.range_violation(reading.price()).is_none() &&
+                            relative_change(reading.price(), price) <= max_price_change
+                    }
+                    None => false,
+                };
+
+                if confirmed {
+                    return PriceVerdict::Confirmed;
+                }
+
+                return PriceVerdict::quarantined(format!(
+                    "price {} changed more than {}% from {}",
+                    price,
+                    max_price_change * 100.0,
+                    last_price
+                ));
+            }
+        }
+
+        PriceVerdict::Accepted
+    }
+
+    fn range_violation(&self, price: f64) -> Option<String> {
+        match (self.min_price, self.max_price) {
+            (Some(min_price), _) if price < min_price => Some(format!(
+                "price {} is less than minimal price {}",
+                price,
+                min_price
+            )),
+            (_, Some(max_price)) if price > max_price => Some(format!(
+                "price {} is greater than maximal price {}",
+                price,
+                max_price
+            )),
+            _ => None,
+        }
+    }
+}
+
+
+impl PriceVerdict {
+    fn quarantined(reason: String) -> PriceVerdict {
+        PriceVerdict::Quarantined { reason }
+    }
+}
+
+
+fn relative_change(from: f64, to: f64) -> f64 {
+    if from == 0.0 && to == 0.0 {
+        0.0
+    } else if from == 0.0 {
+        f64::INFINITY
+    } else {
+        ((to - from) / from).abs()
+    }
+}
@@ -0,0 +1,71 @@
+use std::cmp;
+use std::str::FromStr;
+
+use chrono::TimeZone;
+use chrono::Utc;
+use cron::Schedule as CronSchedule;
+
+
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Interval { seconds: i64 },
+    Cron { schedule: CronSchedule },
+}
+
+
+impl Schedule {
+    pub fn hours(hours: usize) -> Schedule {
+        Schedule::Interval { seconds: hours as i64 * 3600 }
+    }
+
+    pub fn parse(text: &str) -> Result<Schedule, String> {
+        let text = text.trim();
+
+        if let Some(seconds) = parse_interval(text) {
+            return if seconds > 0 {
+                Ok(Schedule::Interval { seconds })
+            } else {
+                Err("interval must be positive".into())
+            };
+        }
+
+        // Cron crate requires seconds field, classic five field expressions start at zero second.
+        let expression = if text.split_whitespace().count() == 5 {
+            format!("0 {}", text)
+        } else {
+            text.to_string()
+        };
+
+        CronSchedule::from_str(&expression)
+            .map(|schedule| Schedule::Cron { schedule })
+            .map_err(|error| format!("{}", error))
+    }
+
+    pub fn next_run(&self, after: i64) -> i64 {
+        match *self {
+            Schedule::Interval { seconds } => after + seconds,
+            Schedule::Cron { ref schedule } => {
+                match schedule.after(&Utc.timestamp(after, 0)).next() {
+                    Some(next_run) => cmp::max(next_run.timestamp(), after + 1),
+                    None => i64::max_value(),
+                }
+            }
+        }
+    }
+}
+
+
+fn parse_interval(text: &str) -> Option<i64> {
+    let unit = text.chars().last()?;
+    let multiplier = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 604_800,
+        _ => return None,
+    };
+    let value: i64 = text[..text.len() - 1].trim().parse().ok()?;
+
+    Some(value * multiplier)
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::time::Duration;
+use std::time::Instant;
+
+use rhai::Array;
+use rhai::AST;
+use rhai::Dynamic;
+use rhai::Engine;
+use rhai::Map;
+use rhai::Scope;
+
+use super::ProductError;
+
+
+#[derive(Debug, Clone)]
+pub struct ScriptInput {
+    pub url: String,
+    pub name: Option<String>,
+    pub price_text: Option<String>,
+    pub price_texts: Vec<String>,
+    pub price: Option<f64>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ScriptOutput {
+    pub name: Option<String>,
+    pub price: Option<f64>,
+    pub available: bool,
+}
+
+
+#[derive(Debug, Default)]
+pub struct ScriptCache {
+    scripts: HashMap<String, AST>,
+}
+
+
+impl ScriptCache {
+    pub fn new() -> ScriptCache {
+        ScriptCache { scripts: HashMap::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.scripts.clear();
+    }
+
+    pub fn run(
+        &mut self,
+        path: &str,
+        timeout: Duration,
+        input: &ScriptInput,
+    ) -> Result<ScriptOutput, ProductError> {
+        if !self.scripts.contains_key(path) {
+            let ast = compile_script(path)?;
+
+            self.scripts.insert(path.to_string(), ast);
+        }
+
+        run_script(path, &self.scripts[path], timeout, input)
+    }
+}
+
+
+fn compile_script(path: &str) -> Result<AST, ProductError> {
+    let mut file = File::open(path).map_err(|error| {
+        ProductError::script_error(format!("can not open `{}`: {}", path, error))
+    })?;
+    let mut source = String::new();
+
+    file.read_to_string(&mut source).map_err(|error| {
+        ProductError::script_error(format!("can not read `{}`: {}", path, error))
+    })?;
+
+    Engine::new().compile(&source).map_err(|error| {
+        ProductError::script_error(format!("`{}`: {}", path, error))
+    })
+}
+
+fn run_script(
+    path: &str,
+    ast: &AST,
+    timeout: Duration,
+    input: &ScriptInput,
+) -> Result<ScriptOutput, ProductError> {
+    let mut engine = Engine::new();
+    let started = Instant::now();
+
+    engine.on_progress(move |&_| if started.elapsed() > timeout {
+        Some(Dynamic::from("execution time limit exceeded"))
+    } else {
+        None
+    });
+
+    let mut scope = Scope::new();
+    scope.push("url", input.url.clone());
+    scope.push_dynamic("name", optional_value(input.name.clone()));
+    scope.push_dynamic("price_text", optional_value(input.price_text.clone()));
+    scope.push_dynamic("price", optional_value(input.price));
+    scope.push(
+        "price_texts",
+        input
+            .price_texts
+            .iter()
+            .cloned()
+            .map(Dynamic::from)
+            .collect::<Array>(),
+    );
+
+    let result: Map = engine.eval_ast_with_scope(&mut scope, ast).map_err(
+        |error| {
+            ProductError::script_error(format!("`{}`: {}", path, error))
+        },
+    )?;
+    let name = match result.get("name") {
+        Some(value) if value.is::<()>() => None,
+        Some(value) => Some(value.as_str().map(String::from).map_err(|_| {
+            ProductError::script_error(format!("`{}`: name must be string", path))
+        })?),
+        None => input.name.clone(),
+    };
+    let price = match result.get("price") {
+        Some(value) if value.is::<()>() => None,
+        Some(value) => Some(to_price(value).ok_or_else(|| {
+            ProductError::script_error(format!("`{}`: price must be number", path))
+        })?),
+        None => input.price,
+    };
+    let available = match result.get("available") {
+        Some(value) => value.as_bool().map_err(|_| {
+            ProductError::script_error(format!("`{}`: available must be boolean", path))
+        })?,
+        None => true,
+    };
+
+    Ok(ScriptOutput {
+        name,
+        price,
+        available,
+    })
+}
+
+fn optional_value<T>(value: Option<T>) -> Dynamic
+where
+    T: Clone + Send + Sync + 'static,
+{
+    match value {
+        Some(value) => Dynamic::from(value),
+        None => Dynamic::from(()),
+    }
+}
+
+fn to_price(value: &Dynamic) -> Option<f64> {
+    if let Ok(value) = value.as_float() {
+        Some(value)
+    } else if let Ok(value) = value.as_int() {
+        Some(value as f64)
+    } else {
+        None
+    }
+}
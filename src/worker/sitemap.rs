@@ -0,0 +1,317 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Error as FmtError;
+use std::fmt::Formatter;
+use std::io::Error as IoError;
+use std::io::Read;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use regex::Regex;
+use url::Url;
+use xml::reader::Error as XmlError;
+use xml::reader::EventReader;
+use xml::reader::XmlEvent;
+
+use super::PriceLoader;
+use super::ProductError;
+use super::SitemapConfig;
+
+
+const MAX_SITEMAP_DEPTH: usize = 4;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+
+#[derive(Debug, Clone)]
+pub struct SitemapEntry {
+    pub url: String,
+    pub lastmod: Option<String>,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum SitemapError {
+    LoadError { description: String },
+    IoError { description: String },
+    XmlError { description: String },
+    InvalidPattern { pattern: String },
+}
+
+
+enum SitemapNode {
+    Sitemap,
+    Url,
+}
+
+
+impl SitemapError {
+    fn invalid_pattern<S>(pattern: S) -> SitemapError
+    where
+        S: Into<String>,
+    {
+        SitemapError::InvalidPattern { pattern: pattern.into() }
+    }
+}
+
+
+impl From<ProductError> for SitemapError {
+    fn from(error: ProductError) -> SitemapError {
+        SitemapError::LoadError { description: format!("{}", error) }
+    }
+}
+
+
+impl From<IoError> for SitemapError {
+    fn from(error: IoError) -> SitemapError {
+        SitemapError::IoError { description: error.description().into() }
+    }
+}
+
+
+impl From<XmlError> for SitemapError {
+    fn from(error: XmlError) -> SitemapError {
+        SitemapError::XmlError { description: format!("{}", error) }
+    }
+}
+
+
+impl Display for SitemapError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            SitemapError::LoadError { ref description } => {
+                write!(f, "Load error: {}", description)
+            }
+            SitemapError::IoError { ref description } => write!(f, "IO error: {}", description),
+            SitemapError::XmlError { ref description } => write!(f, "XML error: {}", description),
+            SitemapError::InvalidPattern { ref pattern } => {
+                write!(f, "Invalid include pattern: {}", pattern)
+            }
+        }
+    }
+}
+
+
+impl Error for SitemapError {
+    fn description(&self) -> &str {
+        match *self {
+            SitemapError::LoadError { .. } => "Load error",
+            SitemapError::IoError { .. } => "IO error",
+            SitemapError::XmlError { .. } => "XML error",
+            SitemapError::InvalidPattern { .. } => "Invalid include pattern",
+        }
+    }
+}
+
+
+pub fn discover(
+    loader: &mut PriceLoader,
+    sitemap: &SitemapConfig,
+    cookies: &Option<HashMap<String, String>>,
+) -> Result<Vec<SitemapEntry>, SitemapError> {
+    let include = Regex::new(&sitemap.include).map_err(|_| {
+        SitemapError::invalid_pattern(sitemap.include.clone())
+    })?;
+    let mut entries = Vec::new();
+
+    collect_entries(loader, &sitemap.url, cookies, &include, &sitemap.url, 0, &mut entries)?;
+
+    let mut entries: Vec<_> = entries
+        .into_iter()
+        .map(|entry| (entry.lastmod.as_ref().and_then(|value| lastmod_time(value)), entry))
+        .collect();
+
+    entries.sort_by(|&(ref a, _), &(ref b, _)| match (a, b) {
+        (&Some(a), &Some(b)) => b.cmp(&a),
+        (&Some(_), &None) => Ordering::Less,
+        (&None, &Some(_)) => Ordering::Greater,
+        (&None, &None) => Ordering::Equal,
+    });
+
+    let mut result: Vec<_> = entries.into_iter().map(|(_, entry)| entry).collect();
+
+    if let Some(limit) = sitemap.limit {
+        result.truncate(limit);
+    }
+
+    Ok(result)
+}
+
+
+fn collect_entries(
+    loader: &mut PriceLoader,
+    url: &str,
+    cookies: &Option<HashMap<String, String>>,
+    include: &Regex,
+    root: &str,
+    depth: usize,
+    result: &mut Vec<SitemapEntry>,
+) -> Result<(), SitemapError> {
+    if depth > MAX_SITEMAP_DEPTH {
+        warn!("Sitemap `{}` is nested too deep, skipped", url);
+
+        return Ok(());
+    }
+
+    info!("Reading sitemap `{}`.", url);
+
+    let page = loader.fetch(url, cookies)?;
+    let content = decompress(page.content)?;
+    let (sitemaps, urls) = parse_sitemap(content.as_slice())?;
+
+    for entry in urls {
+        if !is_web_url(&entry.url) {
+            warn!("Sitemap entry `{}` is not HTTP URL, skipped", entry.url);
+
+            continue;
+        }
+
+        if include.is_match(&entry.url) {
+            result.push(entry);
+        }
+    }
+
+    for entry in sitemaps {
+        if !is_web_url(&entry.url) {
+            warn!("Nested sitemap `{}` is not HTTP URL, skipped", entry.url);
+
+            continue;
+        }
+
+        if !same_host(root, &entry.url) {
+            warn!("Nested sitemap `{}` is on other host, skipped", entry.url);
+
+            continue;
+        }
+
+        let nested =
+            collect_entries(loader, &entry.url, cookies, include, root, depth + 1, result);
+
+        if let Err(error) = nested {
+            warn!("Can not read sitemap `{}`: {}", entry.url, error);
+        }
+    }
+
+    Ok(())
+}
+
+
+fn same_host(root: &str, url: &str) -> bool {
+    match (Url::parse(root), Url::parse(url)) {
+        (Ok(root), Ok(url)) => {
+            match (root.host_str(), url.host_str()) {
+                (Some(root_host), Some(host)) => root_host.eq_ignore_ascii_case(host),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+
+fn is_web_url(url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+        Err(_) => false,
+    }
+}
+
+
+fn lastmod_time(value: &str) -> Option<i64> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.timestamp());
+    }
+
+    let value = if value.ends_with('Z') {
+        format!("{}+00:00", &value[..value.len() - 1])
+    } else {
+        value.to_string()
+    };
+
+    if let Ok(time) = DateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M%:z") {
+        return Some(time.timestamp());
+    }
+
+    let date = match value.len() {
+        4 => format!("{}-01-01", value),
+        7 => format!("{}-01", value),
+        _ => value,
+    };
+
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_hms(0, 0, 0).timestamp())
+}
+
+
+fn decompress(content: Vec<u8>) -> Result<Vec<u8>, SitemapError> {
+    if content.starts_with(&GZIP_MAGIC) {
+        let mut decoder = GzDecoder::new(content.as_slice());
+        let mut result = Vec::new();
+
+        decoder.read_to_end(&mut result)?;
+
+        Ok(result)
+    } else {
+        Ok(content)
+    }
+}
+
+
+fn parse_sitemap<R>(source: R) -> Result<(Vec<SitemapEntry>, Vec<SitemapEntry>), SitemapError>
+where
+    R: Read,
+{
+    let mut sitemaps = Vec::new();
+    let mut urls = Vec::new();
+    let mut node = None;
+    let mut text = String::new();
+    let mut loc = None;
+    let mut lastmod = None;
+
+    for event in EventReader::new(source) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => {
+                match name.local_name.as_str() {
+                    "sitemap" => node = Some(SitemapNode::Sitemap),
+                    "url" => node = Some(SitemapNode::Url),
+                    _ => {}
+                }
+
+                text.clear();
+            }
+            XmlEvent::Characters(value) |
+            XmlEvent::CData(value) => text.push_str(&value),
+            XmlEvent::EndElement { name } => {
+                match name.local_name.as_str() {
+                    "loc" => loc = Some(text.trim().to_string()),
+                    "lastmod" => lastmod = Some(text.trim().to_string()),
+                    "sitemap" | "url" => {
+                        if let Some(url) = loc.take() {
+                            let entry = SitemapEntry {
+                                url,
+                                lastmod: lastmod.take(),
+                            };
+
+                            match node.take() {
+                                Some(SitemapNode::Sitemap) => sitemaps.push(entry),
+                                Some(SitemapNode::Url) => urls.push(entry),
+                                None => {}
+                            }
+                        }
+
+                        lastmod = None;
+                    }
+                    _ => {}
+                }
+
+                text.clear();
+            }
+            _ => {}
+        }
+    }
+
+    Ok((sitemaps, urls))
+}
@@ -1,236 +1,567 @@
+use std::cmp;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::error::Error;
-use std::fmt::Display;
-use std::fmt::Formatter;
-use std::fmt::Result as FmtResult;
-use std::fs::File;
-use std::io::Error as IoError;
-use std::io::Read;
+use std::collections::HashSet;
 use std::io::Result as IoResult;
 use std::path::Path;
 use std::path::PathBuf;
 use std::thread::Builder;
-use std::thread::JoinHandle;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
 use std::thread;
-use std::time::Duration;
+use std::thread::JoinHandle;
 
 use time;
-use toml::de::Error as TomlError;
-use toml;
 
 use database::Database;
 use logger::UnwrapLog;
+use shutdown::Shutdown;
 
 use super::Config;
-use super::ConfigError;
+use super::control::CrawlerEvent;
+use super::CrawlerControl;
+use super::CrawlFilter;
+use super::LoaderMode;
 use super::PriceLoader;
+use super::ProductConfig;
+use super::read_config;
+use super::ReadConfigError;
+use super::sanity::PriceLimits;
+use super::sanity::PriceVerdict;
+use super::schedule::Schedule;
+use super::sitemap;
+use super::watcher::ConfigWatcher;
+
+
+fn discover_products(
+    config: &Config,
+    loader: &mut PriceLoader,
+) -> (Vec<ProductConfig>, HashSet<String>) {
+    let mut products = Vec::new();
+    let mut failed_shops = HashSet::new();
+    let mut urls = product_urls(config);
 
+    for shop in &config.shops {
+        for sitemap in shop.sitemaps.iter().flat_map(|sitemaps| sitemaps) {
+            info!("Discovering products for shop `{}` from `{}`.", shop.name, sitemap.url);
+
+            match sitemap::discover(loader, sitemap, &shop.cookies) {
+                Ok(entries) => {
+                    info!("Found {} products in `{}`.", entries.len(), sitemap.url);
+
+                    for entry in entries {
+                        let url = config.canonical_url(&shop.name, &entry.url);
+
+                        if urls.insert(url.clone()) {
+                            products.push(ProductConfig::new(
+                                shop.name.clone(),
+                                sitemap.category.clone(),
+                                url,
+                            ));
+                        }
+                    }
+                }
+                Err(error) => {
+                    warn!("Sitemap discovery error: {}", error);
 
-#[derive(Debug, Clone)]
-enum ReadConfigError {
-    IoError { description: String },
-    ParsingError {
-        line: Option<usize>,
-        column: Option<usize>,
-    },
-    InvalidConfig { error: ConfigError },
+                    failed_shops.insert(shop.name.clone());
+                }
+            }
+        }
+    }
+
+    (products, failed_shops)
 }
 
 
-impl ReadConfigError {
-    fn invalid_config(error: ConfigError) -> ReadConfigError {
-        ReadConfigError::InvalidConfig { error }
-    }
+fn check_price(
+    database: &Database,
+    limits: &PriceLimits,
+    product_url: &str,
+    price: f64,
+) -> PriceVerdict {
+    let last_accepted = match database.last_accepted_price(product_url) {
+        Ok(last_accepted) => last_accepted,
+        Err(error) => {
+            warn!("Can not read last accepted price: {}", error);
+
+            None
+        }
+    };
+    let last_reading = match database.last_price_reading(product_url) {
+        Ok(last_reading) => last_reading,
+        Err(error) => {
+            warn!("Can not read last price reading: {}", error);
+
+            None
+        }
+    };
+
+    limits.check(price, last_accepted, last_reading.as_ref())
 }
 
 
-impl From<IoError> for ReadConfigError {
-    fn from(error: IoError) -> ReadConfigError {
-        ReadConfigError::IoError { description: error.description().into() }
-    }
+fn scheduled_products(config: &Config, discovered: &[ProductConfig]) -> Vec<ProductConfig> {
+    let urls = product_urls(config);
+    let mut products = config.products.clone();
+
+    products.extend(discovered.iter().filter(|product| !urls.contains(&product.url)).cloned());
+    products
 }
 
 
-impl From<TomlError> for ReadConfigError {
-    fn from(error: TomlError) -> ReadConfigError {
-        ReadConfigError::ParsingError {
-            line: error.line_col().map(|(line, _)| line),
-            column: error.line_col().map(|(_, column)| column),
+fn product_schedule(
+    config: &Config,
+    product: &ProductConfig,
+    default_schedule: &Schedule,
+) -> Schedule {
+    let shop_schedule = config
+        .shops
+        .iter()
+        .find(|shop| shop.name == product.shop_name)
+        .and_then(|shop| shop.schedule.as_ref());
+
+    match product.schedule.as_ref().or(shop_schedule) {
+        Some(schedule) => {
+            match Schedule::parse(schedule) {
+                Ok(schedule) => schedule,
+                Err(error) => {
+                    warn!("Invalid schedule for product `{}`: {}", product.url, error);
+
+                    default_schedule.clone()
+                }
+            }
         }
+        None => default_schedule.clone(),
     }
 }
 
 
-impl Display for ReadConfigError {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        match *self {
-            ReadConfigError::IoError { ref description } => write!(f, "IO error: {}", description),
-            ReadConfigError::ParsingError {
-                line: Some(line),
-                column: Some(column),
-            } => write!(f, "Parsing error at line {} column {}", line, column),
-            ReadConfigError::ParsingError {
-                line: Some(line),
-                column: None,
-            } => write!(f, "Parsing error at line {}", line),
-            ReadConfigError::ParsingError {
-                line: None,
-                column: Some(column),
-            } => write!(f, "Parsing error at column {}", column),
-            ReadConfigError::ParsingError {
-                line: None,
-                column: None,
-            } => write!(f, "Parsing error"),
-            ReadConfigError::InvalidConfig { ref error } => {
-                write!(f, "Invalid configuration: {}", error)
-            }
+fn store_products(
+    database: &Database,
+    config: &Config,
+    discovered: &[ProductConfig],
+    failed_shops: &HashSet<String>,
+    default_schedule: &Schedule,
+    filter: Option<&CrawlFilter>,
+    control: &CrawlerControl,
+    loader: &mut PriceLoader,
+) -> i64 {
+    let next_runs = match database.next_runs() {
+        Ok(next_runs) => next_runs,
+        Err(error) => {
+            warn!("Can not read product schedule: {}", error);
+
+            HashMap::new()
         }
+    };
+    let start_time = time::get_time().sec;
+    let mut wake_time = i64::max_value();
+    let mut products = Vec::new();
+    let mut schedules = Vec::new();
+    let scheduled = scheduled_products(config, discovered);
+    let urls: HashSet<_> = scheduled.iter().map(|product| product.url.clone()).collect();
+
+    control.update_targets(config, &scheduled);
+
+    if let Err(error) = database.update_active_products(&urls, failed_shops) {
+        warn!("Can not update active products: {}", error);
     }
-}
 
+    for product in scheduled {
+        let schedule = product_schedule(config, &product, default_schedule);
+        let next_run = match next_runs.get(&product.url) {
+            Some(&next_run) => cmp::min(next_run, schedule.next_run(start_time)),
+            None => start_time,
+        };
+
+        let selected = match filter {
+            Some(filter) => filter.matches(config, &product),
+            None => next_run <= start_time,
+        };
+
+        if selected {
+            products.push(product);
+            schedules.push(schedule);
+        } else {
+            wake_time = cmp::min(wake_time, next_run);
+        }
+    }
 
-impl Error for ReadConfigError {
-    fn description(&self) -> &str {
-        match *self {
-            ReadConfigError::IoError { .. } => "IO error",
-            ReadConfigError::ParsingError { .. } => "Parsing error",
-            ReadConfigError::InvalidConfig { .. } => "Invalid configuration",
+    if products.is_empty() {
+        if filter.is_some() {
+            warn!("No products match requested crawl.");
         }
+
+        return wake_time;
     }
-}
 
+    let _work = control.work();
 
-fn read_config<P>(path: P) -> Result<Config, ReadConfigError>
-where
-    P: AsRef<Path>,
-{
-    let path = path.as_ref();
+    if control.is_stopping() {
+        return wake_time;
+    }
+
+    info!("Processing {} scheduled products.", products.len());
+    control.start_cycle(products.len(), start_time);
+
+    let next_iteration = match (database.iteration(), filter) {
+        (Ok(Some(iteration)), Some(_)) => iteration,
+        (Ok(Some(iteration)), None) => iteration + 1,
+        (Ok(None), _) => 0,
+        (Err(error), _) => {
+            warn!("Can not read current iteration: {}", error);
+
+            0
+        }
+    };
 
-    info!("Reading file `{}`.", path.display());
+    let processed = crawl_products(database, config, &products, next_iteration, control, loader);
 
-    let mut file = File::open(path)?;
-    let mut content = String::new();
+    if processed < products.len() {
+        info!(
+            "Iteration {} stopped after {} of {} products.",
+            next_iteration,
+            processed,
+            products.len()
+        );
+    }
 
-    file.read_to_string(&mut content)?;
+    if let Err(error) = database.save_iteration(next_iteration) {
+        warn!("Can not write next iteration: {}", error);
+    }
 
-    info!("Parsing configuration from file `{}`.", path.display());
+    let complete_time = time::get_time().sec;
 
-    let config: Config = toml::from_str(&content)?;
+    for (product, schedule) in products.iter().zip(&schedules).take(processed) {
+        let next_run = schedule.next_run(complete_time);
 
-    info!("Validating configuration from file `{}`.", path.display());
+        if let Err(error) = database.save_next_run(&product.url, next_run) {
+            warn!("Can not save next run of product `{}`: {}", product.url, error);
+        }
 
-    if let Some(error) = config.validate() {
-        Err(ReadConfigError::invalid_config(error))
-    } else {
-        Ok(config)
+        wake_time = cmp::min(wake_time, next_run);
     }
-}
 
+    control.complete_cycle(complete_time);
 
-fn store_products(database: &Database, config: &Config, loader: &mut PriceLoader) -> () {
-    info!("Processing products.");
+    wake_time
+}
 
+
+fn crawl_products(
+    database: &Database,
+    config: &Config,
+    products: &[ProductConfig],
+    iteration: i64,
+    control: &CrawlerControl,
+    loader: &mut PriceLoader,
+) -> usize {
     let mut shops = HashMap::new();
 
     for shop in &config.shops {
         shops.insert(shop.name.clone(), shop.clone());
     }
 
-    let next_iteration = match database.iteration() {
-        Ok(Some(iteration)) => iteration + 1,
-        Ok(None) => 0,
-        Err(error) => {
-            warn!("Can not read current iteration: {}", error);
+    let mut selector_stats = BTreeMap::new();
+    let mut processed = 0;
 
-            0
+    for product in products {
+        if control.is_stopping() {
+            info!("Crawler stopped by termination signal.");
+
+            break;
         }
-    };
 
-    for product in &config.products {
         let timestamp = time::get_time().sec;
         let shop_name = &product.shop_name;
-        let shop = shops.get(shop_name).expect("Shop from product not found");
-        let price = loader.load(
-            &product.url,
-            &shop.cookies,
-            &shop.name_selector,
-            &shop.price_selector,
-            shop.price_factor.unwrap_or(1.0),
-            shop.price_index.unwrap_or(0),
-        );
+        let shop = shops
+            .get(shop_name)
+            .expect("Shop from product not found")
+            .with_overrides(product);
+        let price = loader.load(&product.url, &shop);
 
         match price {
             Ok(price) => {
+                if price.name_alternative > 0 {
+                    info!(
+                        "Product `{}` name matched fallback selector #{}",
+                        product.url,
+                        price.name_alternative
+                    );
+                }
+
+                if price.price_alternative > 0 {
+                    info!(
+                        "Product `{}` price matched fallback selector #{}",
+                        product.url,
+                        price.price_alternative
+                    );
+                }
+
+                *selector_stats
+                    .entry((shop_name.clone(), "name", price.name_alternative))
+                    .or_insert(0) += 1;
+                *selector_stats
+                    .entry((shop_name.clone(), "price", price.price_alternative))
+                    .or_insert(0) += 1;
+
+                let limits = PriceLimits::new(product, config.category(&product.category));
+                let quarantine = match check_price(database, &limits, &product.url, price.price) {
+                    PriceVerdict::Accepted => None,
+                    PriceVerdict::Confirmed => {
+                        info!("Product `{}` price change confirmed", product.url);
+
+                        if let Err(error) = database.release_quarantine(&product.url) {
+                            warn!("Can not release quarantined prices: {}", error);
+                        }
+
+                        None
+                    }
+                    PriceVerdict::Quarantined { reason } => {
+                        warn!("Product `{}` price quarantined: {}", product.url, reason);
+
+                        Some(reason)
+                    }
+                };
                 let result = database.save_price(
                     shop_name,
                     &product.category,
                     &product.url,
                     &price.name,
-                    next_iteration,
+                    iteration,
                     timestamp,
                     price.price,
+                    quarantine.as_ref().map(|reason| reason.as_str()),
+                    product.group.as_ref().map(|group| group.as_str()),
                 );
 
-                if let Err(error) = result {
-                    warn!("Can not save product price: {}", error);
+                match result {
+                    Ok(Some(ref name_change)) if name_change.is_substitution() => {
+                        warn!(
+                            "Product `{}` name changed from `{}` to `{}`, possible product \
+                             substitution",
+                            product.url,
+                            name_change.old_name(),
+                            name_change.new_name()
+                        )
+                    }
+                    Ok(Some(ref name_change)) => {
+                        info!(
+                            "Product `{}` name changed from `{}` to `{}`",
+                            product.url,
+                            name_change.old_name(),
+                            name_change.new_name()
+                        )
+                    }
+                    Ok(None) => {}
+                    Err(error) => warn!("Can not save product price: {}", error),
+                }
+
+                let tags = product.tags.as_ref().map(|tags| tags.as_slice()).unwrap_or(&[]);
+
+                if let Err(error) = database.save_tags(&product.url, tags) {
+                    warn!("Can not save product tags: {}", error);
                 }
             }
             Err(error) => warn!("Product parsing error: {}", error),
         }
+
+        control.advance();
+        processed += 1;
     }
 
-    if let Err(error) = database.save_iteration(next_iteration) {
-        warn!("Can not write next iteration: {}", error);
+    for ((shop_name, kind, alternative), count) in selector_stats {
+        info!(
+            "Shop `{}` {} selector #{} matched {} products",
+            shop_name,
+            kind,
+            alternative,
+            count
+        );
     }
+
+    processed
 }
 
 
-fn run(database: Database, config_path: PathBuf, period: time::Duration) {
+fn run(
+    database: Database,
+    config_path: PathBuf,
+    period: usize,
+    mode: LoaderMode,
+    control: CrawlerControl,
+    receiver: Receiver<CrawlerEvent>,
+) {
     info!("Creating price loader.");
 
-    let mut price_loader = PriceLoader::new().unwrap_log("Price loader creation error");
+    let mut price_loader = PriceLoader::new(mode).unwrap_log("Price loader creation error");
+    let sender = control.sender().unwrap_log("Can not create crawler event sender");
+    let mut config_watcher = match ConfigWatcher::new(&config_path, sender) {
+        Ok(config_watcher) => Some(config_watcher),
+        Err(error) => {
+            warn!("Can not watch configuration file: {}", error);
 
-    info!("Starting price update loop.");
+            None
+        }
+    };
+    let default_schedule = Schedule::hours(period);
+    let discovery_period = period as i64 * 3600;
+    let mut discovered = Vec::new();
+    let mut failed_shops = HashSet::new();
+    let mut discovery_time = 0;
+    let mut filter = None;
+
+    info!("Starting price update scheduler.");
 
     loop {
-        info!("Starting price update cycle.");
+        if control.is_stopping() {
+            info!("Price update scheduler stopped.");
+
+            return;
+        }
 
-        let start_time = time::now();
+        let mut wake_time = time::get_time().sec + discovery_period;
 
         match read_config(&config_path) {
-            Ok(config) => store_products(&database, &config, &mut price_loader),
+            Ok(config) => {
+                update_watcher(&mut config_watcher, &config);
+                price_loader.reset_scripts();
+
+                if time::get_time().sec >= discovery_time {
+                    let (products, shops) = discover_products(&config, &mut price_loader);
+
+                    discovered = products;
+                    failed_shops = shops;
+                    discovery_time = time::get_time().sec + discovery_period;
+                }
+
+                let next_run = store_products(
+                    &database,
+                    &config,
+                    &discovered,
+                    &failed_shops,
+                    &default_schedule,
+                    filter.as_ref(),
+                    &control,
+                    &mut price_loader,
+                );
+
+                wake_time = cmp::min(discovery_time, next_run);
+            }
             Err(error) => warn!("Error reading configuration: {}", error),
         }
 
-        let complete_time = time::now();
-        let update_duration = complete_time - start_time;
+        control.finish_request();
+        filter = wait_for_event(&receiver, &control, wake_time);
+    }
+}
 
-        if update_duration < period {
-            let sleep_time = period.num_seconds() - update_duration.num_seconds();
 
-            if sleep_time > 0 {
-                info!("Sleeping to next update for {} seconds", sleep_time);
+fn wait_for_event(
+    receiver: &Receiver<CrawlerEvent>,
+    control: &CrawlerControl,
+    wake_time: i64,
+) -> Option<CrawlFilter> {
+    let delay = wake_time - time::get_time().sec;
 
-                thread::sleep(Duration::from_secs(sleep_time as u64));
-            }
-        } else {
-            warn!("Period is too short to update all the products");
+    if delay > 0 {
+        info!("Sleeping to next update for {} seconds", delay);
+    }
+
+    let timeout = time::Duration::seconds(wake_time - time::get_time().sec)
+        .to_std()
+        .unwrap_or_default();
+
+    match receiver.recv_timeout(timeout) {
+        Ok(CrawlerEvent::ConfigChanged) => {
+            info!("Rescheduling products after configuration change.");
+
+            None
+        }
+        Ok(CrawlerEvent::Crawl { filter }) => {
+            info!("Starting requested crawl.");
+            control.accept_request(&filter);
+
+            Some(filter)
+        }
+        Ok(CrawlerEvent::Stop) |
+        Err(RecvTimeoutError::Timeout) => None,
+        Err(RecvTimeoutError::Disconnected) => {
+            thread::sleep(timeout);
+
+            None
+        }
+    }
+}
+
+
+fn product_urls(config: &Config) -> HashSet<String> {
+    config.products.iter().map(|product| product.url.clone()).collect()
+}
+
+
+fn update_watcher(config_watcher: &mut Option<ConfigWatcher>, config: &Config) {
+    if let Some(ref mut config_watcher) = *config_watcher {
+        if let Err(error) = config_watcher.update(config) {
+            warn!("Can not watch included configuration files: {}", error);
         }
     }
 }
 
 
-pub fn start_crawler<P>(database: Database, path: P, period: usize) -> IoResult<JoinHandle<()>>
+pub fn crawl_once<P>(
+    database: &Database,
+    path: P,
+    period: usize,
+    mode: LoaderMode,
+    shutdown: Shutdown,
+) -> Result<(), ReadConfigError>
+where
+    P: AsRef<Path>,
+{
+    let mut price_loader = PriceLoader::new(mode).unwrap_log("Price loader creation error");
+    let config = read_config(path)?;
+    let (discovered, failed_shops) = discover_products(&config, &mut price_loader);
+    let (control, _) = CrawlerControl::new(shutdown);
+
+    store_products(
+        database,
+        &config,
+        &discovered,
+        &failed_shops,
+        &Schedule::hours(period),
+        None,
+        &control,
+        &mut price_loader,
+    );
+
+    Ok(())
+}
+
+
+pub fn start_crawler<P>(
+    database: Database,
+    path: P,
+    period: usize,
+    mode: LoaderMode,
+    shutdown: Shutdown,
+) -> IoResult<(CrawlerControl, JoinHandle<()>)>
 where
     P: AsRef<Path>,
 {
     info!("Starting background loader thread.");
 
     let config_path = path.as_ref().to_path_buf();
-    let period = time::Duration::hours(period as i64);
+    let (control, receiver) = CrawlerControl::new(shutdown);
+    let thread_control = control.clone();
 
-    Builder::new()
+    let handle = Builder::new()
         .name("crawler".into())
         .stack_size(512 * 1024)
-        .spawn(move || run(database, config_path, period))
+        .spawn(move || {
+            run(database, config_path, period, mode, thread_control, receiver)
+        })?;
+
+    Ok((control, handle))
 }
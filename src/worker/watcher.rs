@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Sender;
+use std::thread::Builder;
+use std::time::Duration;
+
+use notify::DebouncedEvent;
+use notify::Error as NotifyError;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Result as NotifyResult;
+use notify::watcher;
+use notify::Watcher;
+
+use super::Config;
+use super::control::CrawlerEvent;
+
+
+const DEBOUNCE_DELAY: u64 = 2;
+
+
+pub struct ConfigWatcher {
+    watcher: RecommendedWatcher,
+    directories: HashSet<PathBuf>,
+    targets: Arc<Mutex<WatchTargets>>,
+}
+
+
+#[derive(Debug, Default)]
+struct WatchTargets {
+    files: HashSet<PathBuf>,
+    directories: HashSet<PathBuf>,
+}
+
+
+impl ConfigWatcher {
+    pub fn new(config_path: &Path, sender: Sender<CrawlerEvent>) -> NotifyResult<ConfigWatcher> {
+        let (notify_sender, notify_receiver) = channel();
+        let watcher = watcher(notify_sender, Duration::from_secs(DEBOUNCE_DELAY))?;
+        let targets = Arc::new(Mutex::new(WatchTargets::default()));
+        let thread_targets = targets.clone();
+
+        Builder::new()
+            .name("config-watcher".into())
+            .spawn(move || for event in notify_receiver {
+                let path = match event {
+                    DebouncedEvent::Create(path) |
+                    DebouncedEvent::Write(path) |
+                    DebouncedEvent::Remove(path) |
+                    DebouncedEvent::Rename(_, path) => path,
+                    DebouncedEvent::Error(error, _) => {
+                        warn!("Configuration watcher error: {}", error);
+
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let is_target = match thread_targets.lock() {
+                    Ok(targets) => targets.contains(&path),
+                    Err(_) => false,
+                };
+
+                if is_target {
+                    info!("Configuration file `{}` changed.", path.display());
+
+                    if sender.send(CrawlerEvent::ConfigChanged).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(NotifyError::Io)?;
+
+        let mut result = ConfigWatcher {
+            watcher,
+            directories: HashSet::new(),
+            targets,
+        };
+
+        result.watch_file(&absolute_path(config_path))?;
+
+        Ok(result)
+    }
+
+    pub fn update(&mut self, config: &Config) -> NotifyResult<()> {
+        for file in &config.sources {
+            self.watch_file(file)?;
+        }
+
+        for directory in &config.include_directories {
+            self.watch_directory(directory)?;
+
+            if let Ok(mut targets) = self.targets.lock() {
+                targets.directories.insert(directory.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn watch_file(&mut self, file: &Path) -> NotifyResult<()> {
+        if let Some(directory) = file.parent() {
+            self.watch_directory(directory)?;
+        }
+
+        if let Ok(mut targets) = self.targets.lock() {
+            targets.files.insert(file.to_path_buf());
+        }
+
+        Ok(())
+    }
+
+    fn watch_directory(&mut self, directory: &Path) -> NotifyResult<()> {
+        if !self.directories.contains(directory) {
+            info!("Watching configuration directory `{}`.", directory.display());
+
+            self.watcher.watch(directory, RecursiveMode::NonRecursive)?;
+            self.directories.insert(directory.to_path_buf());
+        }
+
+        Ok(())
+    }
+}
+
+
+impl WatchTargets {
+    fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path) ||
+            match path.parent() {
+                Some(directory) => self.directories.contains(directory),
+                None => false,
+            }
+    }
+}
+
+
+fn absolute_path(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        path
+    } else if let Ok(directory) = env::current_dir() {
+        directory.join(path)
+    } else {
+        path.to_path_buf()
+    }
+}